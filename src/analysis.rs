@@ -0,0 +1,4 @@
+pub mod attribution;
+pub mod damage_formula;
+pub mod rotation;
+pub mod speed;
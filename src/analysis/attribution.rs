@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+
+use crate::battle::BattleContext;
+use crate::models::misc::{Entity, Stat, Team};
+
+// Stats on the attacker and on the defender that scale a hit and can be granted by a support
+const ATTACKER_STATS: &[&str] = &[
+    "Attack",
+    "AllDamageTypeAddedRatio",
+    "CriticalChance",
+    "CriticalDamage",
+    "AllDamageTypePenetrate",
+];
+const DEFENDER_STATS: &[&str] = &["Defense", "AllDamageTypeResistance", "AllDamageTypeTakenRatio"];
+
+// DEF multiplier constant for a level 80 attacker, (level + 20) * 10
+const ATTACKER_DEFENSE_CONSTANT: f64 = 1000.;
+
+// A stat change made by a support during its skill window
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuffRecord {
+    pub source_avatar_id: u32,
+    pub target: Entity,
+    pub stat: String,
+    pub value_before: f64,
+    pub delta: f64,
+    pub action_value: f64,
+    // None while still active
+    pub expired_action_value: Option<f64>,
+}
+
+impl BuffRecord {
+    // True when the target's stat went back to what it was before the buff
+    fn is_undone_by(&self, value: f64) -> bool {
+        if self.delta > 0. {
+            value <= self.value_before + f64::EPSILON
+        } else {
+            value >= self.value_before - f64::EPSILON
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DamageAttribution {
+    pub avatar_id: u32,
+    pub name: String,
+    pub damage: f64,
+    // Damage of other avatars' hits credited to this one
+    pub enabled_damage: f64,
+    // Damage of this avatar's hits credited to others
+    pub enabled_by_others: f64,
+    pub attributed_damage: f64,
+}
+
+fn is_favorable(target: &Entity, stat: &str, delta: f64) -> bool {
+    match target.team {
+        Team::Player => ATTACKER_STATS.contains(&stat) && delta > 0.,
+        Team::Enemy => match stat {
+            "Defense" | "AllDamageTypeResistance" => delta < 0.,
+            "AllDamageTypeTakenRatio" => delta > 0.,
+            _ => false,
+        },
+    }
+}
+
+impl BattleContext {
+    fn stat_value(&self, entity: &Entity, stat: &str) -> f64 {
+        self.stats_of(entity)
+            .and_then(|stats| stats.get(stat))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    // Sum of a source's active buffs on a target's stat
+    fn active_delta(&self, source_avatar_id: u32, target: &Entity, stat: &str) -> f64 {
+        self.buff_history
+            .iter()
+            .filter(|buff| {
+                buff.expired_action_value.is_none()
+                    && buff.source_avatar_id == source_avatar_id
+                    && buff.target == *target
+                    && buff.target.team == target.team
+                    && buff.stat == stat
+            })
+            .map(|buff| buff.delta)
+            .sum()
+    }
+
+    // Called on every stat change before the entity's stats are updated
+    pub(crate) fn track_buff(&mut self, target: &Entity, stat: &Stat) {
+        let kind = stat.kind();
+        if !ATTACKER_STATS.contains(&kind) && !DEFENDER_STATS.contains(&kind) {
+            return;
+        }
+
+        let value = stat.value();
+        let action_value = self.action_value;
+        for buff in self.buff_history.iter_mut().filter(|buff| {
+            buff.expired_action_value.is_none()
+                && buff.target == *target
+                && buff.target.team == target.team
+                && buff.stat == kind
+        }) {
+            if buff.is_undone_by(value) {
+                buff.expired_action_value = Some(action_value);
+            }
+        }
+
+        let Some(value_before) = self
+            .stats_of(target)
+            .and_then(|stats| stats.get(kind))
+            .copied()
+        else {
+            return;
+        };
+        let delta = value - value_before;
+        if !is_favorable(target, kind, delta) {
+            return;
+        }
+
+        // Credit the avatar whose skill is ongoing in the current turn, unless it buffed itself
+        let turn_battle_id = self.entity_turn_history.len() as u32;
+        let Some(source_avatar_id) = self
+            .skill_history
+            .last()
+            .filter(|skill| skill.turn_battle_id == turn_battle_id)
+            .map(|skill| skill.avatar_id)
+            .filter(|avatar_id| !(target.team == Team::Player && target.uid == *avatar_id))
+        else {
+            return;
+        };
+
+        self.buff_history.push(BuffRecord {
+            source_avatar_id,
+            target: target.clone(),
+            stat: kind.to_string(),
+            value_before,
+            delta,
+            action_value,
+            expired_action_value: None,
+        });
+    }
+
+    // Splits a hit between its attacker and the supports whose active buffs scaled it.
+    // Each support is credited the share of the hit that would be lost without its buffs.
+    pub(crate) fn attribute_hit(&mut self, attacker: &Entity, defender: Option<&Entity>, damage: f64) {
+        let Some(attacker_index) = self
+            .avatar_lineup
+            .iter()
+            .position(|avatar| avatar.id == attacker.uid)
+        else {
+            return;
+        };
+
+        let attack = self.stat_value(attacker, "Attack");
+        let damage_bonus = self.stat_value(attacker, "AllDamageTypeAddedRatio");
+        let crit_chance = self.stat_value(attacker, "CriticalChance").clamp(0., 1.);
+        let crit_damage = self.stat_value(attacker, "CriticalDamage");
+        let penetration = self.stat_value(attacker, "AllDamageTypePenetrate");
+        let (defense, resistance, vulnerability) = defender.map_or((0., 0., 0.), |defender| {
+            (
+                self.stat_value(defender, "Defense"),
+                self.stat_value(defender, "AllDamageTypeResistance"),
+                self.stat_value(defender, "AllDamageTypeTakenRatio"),
+            )
+        });
+
+        let mut credits = Vec::new();
+        for (support_index, support) in self.avatar_lineup.iter().enumerate() {
+            if support_index == attacker_index {
+                continue;
+            }
+            let attacker_delta = |stat: &str| self.active_delta(support.id, attacker, stat);
+            let defender_delta =
+                |stat: &str| defender.map_or(0., |defender| self.active_delta(support.id, defender, stat));
+
+            let mut ratio = 1.;
+            if attack > 0. {
+                ratio *= (attack - attacker_delta("Attack")) / attack;
+            }
+            ratio *= (1. + damage_bonus - attacker_delta("AllDamageTypeAddedRatio"))
+                / (1. + damage_bonus);
+            ratio *= (1.
+                + (crit_chance - attacker_delta("CriticalChance")).clamp(0., 1.)
+                    * (crit_damage - attacker_delta("CriticalDamage")))
+                / (1. + crit_chance * crit_damage);
+            let resistance_multiplier = (1. - resistance + penetration).max(0.1);
+            ratio *= (1. - (resistance - defender_delta("AllDamageTypeResistance"))
+                + (penetration - attacker_delta("AllDamageTypePenetrate")))
+            .max(0.1)
+                / resistance_multiplier;
+            ratio *= (ATTACKER_DEFENSE_CONSTANT + defense)
+                / (ATTACKER_DEFENSE_CONSTANT + (defense - defender_delta("Defense")).max(0.));
+            ratio *= (1. + vulnerability - defender_delta("AllDamageTypeTakenRatio"))
+                / (1. + vulnerability);
+
+            let credit = damage * (1. - ratio.clamp(0., 1.));
+            if credit > 0. {
+                credits.push((support_index, credit));
+            }
+        }
+
+        // Supports can't be credited more than the hit itself
+        let total_credit = credits.iter().map(|(_, credit)| credit).sum::<f64>();
+        let scale = if total_credit > damage { damage / total_credit } else { 1. };
+        for (support_index, credit) in credits {
+            let credit = credit * scale;
+            self.enabled_damages[support_index] += credit;
+            self.enabled_by_others[attacker_index] += credit;
+        }
+    }
+
+    pub fn damage_attribution(&self) -> Vec<DamageAttribution> {
+        self.avatar_lineup
+            .iter()
+            .enumerate()
+            .map(|(i, avatar)| {
+                let damage = self.real_time_damages.get(i).copied().unwrap_or_default();
+                let enabled_damage = self.enabled_damages.get(i).copied().unwrap_or_default();
+                let enabled_by_others = self.enabled_by_others.get(i).copied().unwrap_or_default();
+                DamageAttribution {
+                    avatar_id: avatar.id,
+                    name: avatar.name.clone(),
+                    damage,
+                    enabled_damage,
+                    enabled_by_others,
+                    attributed_damage: damage - enabled_by_others + enabled_damage,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::battle::BattleContext;
+use crate::models::misc::{Element, Entity};
+
+const DEFAULT_ATTACKER_LEVEL: f64 = 80.;
+const DEFAULT_DEFENDER_LEVEL: f64 = 80.;
+// Unbroken enemies take 10% less damage
+const UNBROKEN_TOUGHNESS_MULTIPLIER: f64 = 0.9;
+// Hits further than this from their skill's expected damage are flagged
+const DEVIATION_TOLERANCE: f64 = 0.05;
+const SKILL_MULTIPLIERS_FILENAME: &str = "skill_multipliers.ron";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttackerStats {
+    pub level: f64,
+    pub attack: f64,
+    // Includes the bonuses for the hit's element and attack type
+    pub damage_bonus: f64,
+    pub crit_damage: f64,
+    // Includes the penetration of the hit's element
+    pub penetration: f64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefenderStats {
+    pub level: f64,
+    pub defense: f64,
+    // Includes the resistance and vulnerability to the hit's element
+    pub resistance: f64,
+    pub vulnerability: f64,
+    pub damage_reduction: f64,
+    pub toughness_broken: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedDamage {
+    pub non_crit: f64,
+    pub crit: f64,
+}
+
+pub fn defense_multiplier(attacker: &AttackerStats, defender: &DefenderStats) -> f64 {
+    // Enemies without a reported DEF use the base DEF of their level
+    let defense = if defender.defense > 0. {
+        defender.defense
+    } else {
+        200. + 10. * defender.level
+    };
+    1. - defense / (defense + 200. + 10. * attacker.level)
+}
+
+pub fn resistance_multiplier(attacker: &AttackerStats, defender: &DefenderStats) -> f64 {
+    (1. - (defender.resistance - attacker.penetration)).clamp(0.1, 2.)
+}
+
+// Damage dealt per unit of skill multiplier on a non-crit hit
+pub fn damage_per_multiplier(attacker: &AttackerStats, defender: &DefenderStats) -> f64 {
+    let toughness_multiplier = if defender.toughness_broken {
+        1.
+    } else {
+        UNBROKEN_TOUGHNESS_MULTIPLIER
+    };
+    attacker.attack
+        * (1. + attacker.damage_bonus)
+        * defense_multiplier(attacker, defender)
+        * resistance_multiplier(attacker, defender)
+        * (1. + defender.vulnerability)
+        * (1. - defender.damage_reduction).max(0.)
+        * toughness_multiplier
+}
+
+pub fn expected_damage(
+    multiplier: f64,
+    attacker: &AttackerStats,
+    defender: &DefenderStats,
+) -> ExpectedDamage {
+    let non_crit = multiplier * damage_per_multiplier(attacker, defender);
+    ExpectedDamage {
+        non_crit,
+        crit: non_crit * (1. + attacker.crit_damage),
+    }
+}
+
+// Skill multiplier that would explain the damage, if it didn't crit and if it did
+pub fn implied_multipliers(
+    damage: f64,
+    attacker: &AttackerStats,
+    defender: &DefenderStats,
+) -> Option<(f64, f64)> {
+    let per_multiplier = damage_per_multiplier(attacker, defender);
+    (per_multiplier > 0.).then(|| {
+        let non_crit = damage / per_multiplier;
+        (non_crit, non_crit / (1. + attacker.crit_damage))
+    })
+}
+
+// Multiplier of a single hit of each skill, keyed by avatar id then skill name as shown in game.
+// Hits of skills missing from the table are recorded but never flagged
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct SkillMultipliers(pub HashMap<u32, HashMap<String, f64>>);
+
+impl SkillMultipliers {
+    pub fn get(&self, avatar_id: u32, skill_name: &str) -> Option<f64> {
+        self.0
+            .get(&avatar_id)
+            .and_then(|skills| skills.get(skill_name))
+            .copied()
+    }
+
+    // Read from the data directory, empty if the file is missing or invalid
+    fn load() -> Self {
+        let Some(proj_dirs) = ProjectDirs::from("", "", env!("CARGO_PKG_NAME")) else {
+            log::warn!("No data directory to load {SKILL_MULTIPLIERS_FILENAME} from");
+            return Self::default();
+        };
+        let path = proj_dirs.data_local_dir().join(SKILL_MULTIPLIERS_FILENAME);
+        if !path.exists() {
+            log::warn!(
+                "{} not found, hits won't be checked against skill multipliers",
+                path.display()
+            );
+            return Self::default();
+        }
+        match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|buffer| Ok(ron::from_str(&buffer)?))
+        {
+            Ok(multipliers) => multipliers,
+            Err(e) => {
+                log::error!("Failed to load {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+}
+
+// Loaded on the first export, restart to pick up changes to the file
+pub static SKILL_MULTIPLIERS: LazyLock<SkillMultipliers> = LazyLock::new(SkillMultipliers::load);
+
+// Stats captured when a hit landed
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HitRecord {
+    pub attacker: Entity,
+    pub defender: Option<Entity>,
+    pub action_value: f64,
+    pub damage: f64,
+    pub damage_type: isize,
+    pub element: Option<Element>,
+    pub skill_name: Option<String>,
+    pub attacker_stats: AttackerStats,
+    pub defender_stats: DefenderStats,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HitCheck {
+    pub hit: HitRecord,
+    pub implied_multiplier: Option<f64>,
+    pub crit: Option<bool>,
+    // Multiplier of the skill from the skill multiplier table
+    pub skill_multiplier: Option<f64>,
+    pub expected: Option<ExpectedDamage>,
+    // Relative distance to the closest expected value
+    pub deviation: Option<f64>,
+    pub flagged: bool,
+}
+
+// Type specific damage bonus for RPG.GameCore.AttackType values
+fn attack_type_bonus_stat(damage_type: isize) -> Option<&'static str> {
+    match damage_type {
+        1 => Some("ExtraNormalDamageAddedRatio1"),
+        2 => Some("ExtraSkillDamageAddedRatio1"),
+        3 => Some("ExtraUltraDamageAddedRatio1"),
+        5 => Some("DotDamageAddedRatio"),
+        9 => Some("ExtraInsertDamageAddedRatio1"),
+        _ => None,
+    }
+}
+
+// Prefix of the element specific AbilityProperty names, e.g. FireAddedRatio
+fn element_stat_prefix(element: Element) -> &'static str {
+    match element {
+        Element::Physical => "Physical",
+        Element::Fire => "Fire",
+        Element::Ice => "Ice",
+        Element::Lightning => "Thunder",
+        Element::Wind => "Wind",
+        Element::Quantum => "Quantum",
+        Element::Imaginary => "Imaginary",
+    }
+}
+
+impl BattleContext {
+    fn reported_stat(&self, entity: &Entity, stat: &str) -> Option<f64> {
+        self.stats_of(entity)
+            .and_then(|stats| stats.get(stat))
+            .copied()
+    }
+
+    pub(crate) fn record_hit(
+        &mut self,
+        attacker: &Entity,
+        defender: Option<&Entity>,
+        damage: f64,
+        damage_type: isize,
+        element: Option<Element>,
+    ) {
+        let attacker_stat = |stat: &str| self.reported_stat(attacker, stat).unwrap_or_default();
+        // Element specific stats are added to their AllDamageType counterpart
+        let element_stat = |entity: &Entity, suffix: &str| {
+            element.map_or(0., |element| {
+                let stat = format!("{}{}", element_stat_prefix(element), suffix);
+                self.reported_stat(entity, &stat).unwrap_or_default()
+            })
+        };
+        let type_bonus = attack_type_bonus_stat(damage_type).map_or(0., attacker_stat);
+        let attacker_stats = AttackerStats {
+            level: self
+                .reported_stat(attacker, "Level")
+                .unwrap_or(DEFAULT_ATTACKER_LEVEL),
+            attack: attacker_stat("Attack"),
+            damage_bonus: attacker_stat("AllDamageTypeAddedRatio")
+                + element_stat(attacker, "AddedRatio")
+                + type_bonus,
+            crit_damage: attacker_stat("CriticalDamage"),
+            penetration: attacker_stat("AllDamageTypePenetrate")
+                + element_stat(attacker, "Penetrate"),
+        };
+
+        let defender_stats = defender.map_or_else(DefenderStats::default, |defender| {
+            let defender_stat = |stat: &str| self.reported_stat(defender, stat).unwrap_or_default();
+            // Enemies without toughness can't be broken
+            let toughness_broken = self
+                .reported_stat(defender, "MaxStance")
+                .is_some_and(|max_toughness| max_toughness > 0.)
+                && self
+                    .reported_stat(defender, "CurrentStance")
+                    .is_some_and(|toughness| toughness <= 0.);
+            DefenderStats {
+                level: self
+                    .reported_stat(defender, "Level")
+                    .unwrap_or(DEFAULT_DEFENDER_LEVEL),
+                defense: defender_stat("Defense"),
+                resistance: defender_stat("AllDamageTypeResistance")
+                    + element_stat(defender, "Resistance"),
+                vulnerability: defender_stat("AllDamageTypeTakenRatio")
+                    + element_stat(defender, "TakenRatio"),
+                damage_reduction: defender_stat("AllDamageReduce"),
+                toughness_broken,
+            }
+        });
+
+        // DoTs, counters and other hits without a skill of their own in this turn are left
+        // unmatched rather than checked against the attacker's last skill
+        let turn_battle_id = self.entity_turn_history.len() as u32;
+        let skill_name = self
+            .skill_history
+            .iter()
+            .rev()
+            .take_while(|skill| skill.turn_battle_id == turn_battle_id)
+            .find(|skill| {
+                skill.avatar_id == attacker.uid && skill.skill_type as isize == damage_type
+            })
+            .map(|skill| skill.skill_name.clone());
+
+        self.hit_history.push(HitRecord {
+            attacker: attacker.clone(),
+            defender: defender.cloned(),
+            action_value: self.action_value,
+            damage,
+            damage_type,
+            element,
+            skill_name,
+            attacker_stats,
+            defender_stats,
+        });
+    }
+
+    // Checks every hit against the expected damage of its skill's multiplier. Hits matching
+    // neither their non-crit nor crit expectation point at a hidden multiplier or a stat
+    // that wasn't captured.
+    pub fn hit_checks(&self, multipliers: &SkillMultipliers) -> Vec<HitCheck> {
+        self.hit_history
+            .iter()
+            .map(|hit| {
+                let implied =
+                    implied_multipliers(hit.damage, &hit.attacker_stats, &hit.defender_stats);
+                let skill_multiplier = hit
+                    .skill_name
+                    .as_deref()
+                    .and_then(|skill_name| multipliers.get(hit.attacker.uid, skill_name));
+                let expected = skill_multiplier.map(|multiplier| {
+                    expected_damage(multiplier, &hit.attacker_stats, &hit.defender_stats)
+                });
+
+                let closest = expected
+                    .filter(|expected| expected.non_crit > 0.)
+                    .map(|expected| {
+                        let non_crit_deviation =
+                            (hit.damage - expected.non_crit) / expected.non_crit;
+                        let crit_deviation = (hit.damage - expected.crit) / expected.crit;
+                        if non_crit_deviation.abs() <= crit_deviation.abs() {
+                            (false, non_crit_deviation)
+                        } else {
+                            (true, crit_deviation)
+                        }
+                    });
+
+                HitCheck {
+                    hit: hit.clone(),
+                    implied_multiplier: implied.map(|(non_crit, crit)| {
+                        if closest.is_some_and(|(crit_hit, _)| crit_hit) {
+                            crit
+                        } else {
+                            non_crit
+                        }
+                    }),
+                    crit: closest.map(|(crit, _)| crit),
+                    skill_multiplier,
+                    expected,
+                    deviation: closest.map(|(_, deviation)| deviation),
+                    // Only hits with a known multiplier can be told apart from expected ones
+                    flagged: skill_multiplier.is_some()
+                        && closest
+                            .is_none_or(|(_, deviation)| deviation.abs() > DEVIATION_TOLERANCE),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lv. 80 attacker hitting a Lv. 95 enemy that has no reported DEF
+    fn attacker() -> AttackerStats {
+        AttackerStats {
+            level: 80.,
+            attack: 3000.,
+            damage_bonus: 0.5,
+            crit_damage: 1.,
+            penetration: 0.1,
+        }
+    }
+
+    fn defender(toughness_broken: bool) -> DefenderStats {
+        DefenderStats {
+            level: 95.,
+            defense: 0.,
+            resistance: 0.2,
+            vulnerability: 0.1,
+            damage_reduction: 0.1,
+            toughness_broken,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn defense_multiplier_uses_level_base_defense() {
+        // Base DEF of 1150 at Lv. 95, 1 - 1150 / (1150 + 200 + 10 * 80)
+        assert_close(
+            defense_multiplier(&attacker(), &defender(true)),
+            1000. / 2150.,
+        );
+
+        let defender = DefenderStats {
+            defense: 800.,
+            ..defender(true)
+        };
+        assert_close(defense_multiplier(&attacker(), &defender), 1000. / 1800.);
+    }
+
+    #[test]
+    fn resistance_multiplier_is_clamped() {
+        assert_close(resistance_multiplier(&attacker(), &defender(true)), 0.9);
+
+        let defender = DefenderStats {
+            resistance: 2.,
+            ..defender(true)
+        };
+        assert_close(resistance_multiplier(&attacker(), &defender), 0.1);
+    }
+
+    #[test]
+    fn expected_damage_of_broken_and_unbroken_hits() {
+        // 3000 * 1.5 * 1000/2150 * 0.9 * 1.1 * 0.9 per unit of multiplier
+        let broken = expected_damage(2., &attacker(), &defender(true));
+        assert_close(broken.non_crit, 3729.767441860466);
+        assert_close(broken.crit, 7459.534883720932);
+
+        let unbroken = expected_damage(2., &attacker(), &defender(false));
+        assert_close(unbroken.non_crit, 3356.7906976744193);
+        assert_close(unbroken.crit, 6713.581395348839);
+    }
+
+    #[test]
+    fn implied_multipliers_of_a_crit_hit() {
+        let (non_crit, crit) =
+            implied_multipliers(7459.534883720932, &attacker(), &defender(true)).unwrap();
+        assert_close(non_crit, 4.);
+        assert_close(crit, 2.);
+
+        let no_attack = AttackerStats {
+            attack: 0.,
+            ..attacker()
+        };
+        assert!(implied_multipliers(1000., &no_attack, &defender(true)).is_none());
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::battle::{BattleContext, SkillCategory, SkillHistoryEntry, cycle_at};
+
+// How many of each avatar's most damaging sequences are reported
+const TOP_SEQUENCE_COUNT: usize = 3;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillUseCounts {
+    pub basic: u32,
+    pub skill: u32,
+    pub ultimate: u32,
+    pub talent: u32,
+}
+
+impl SkillUseCounts {
+    pub fn values(&self) -> [(&'static str, u32); 4] {
+        [
+            ("basic", self.basic),
+            ("skill", self.skill),
+            ("ultimate", self.ultimate),
+            ("talent", self.talent),
+        ]
+    }
+
+    fn add(&mut self, category: SkillCategory) {
+        match category {
+            SkillCategory::Basic => self.basic += 1,
+            SkillCategory::Skill => self.skill += 1,
+            SkillCategory::Ultimate => self.ultimate += 1,
+            SkillCategory::Talent => self.talent += 1,
+            SkillCategory::Other => {}
+        }
+    }
+}
+
+// Basics and skills leading up to an ultimate, e.g. Skill-Skill-Ult
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillSequence {
+    pub sequence: String,
+    pub action_value: f64,
+    pub damage: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvatarRotationReport {
+    pub avatar_id: u32,
+    pub name: String,
+    // Most common sequence
+    pub rotation: Option<String>,
+    // Index w/ cycle
+    pub uses_per_cycle: Vec<SkillUseCounts>,
+    // AV of ultimates that dealt no damage and changed no ally stat
+    pub wasted_ultimates: Vec<f64>,
+    pub top_sequences: Vec<SkillSequence>,
+}
+
+fn category_label(category: SkillCategory) -> &'static str {
+    match category {
+        SkillCategory::Basic => "Basic",
+        SkillCategory::Skill => "Skill",
+        SkillCategory::Ultimate => "Ult",
+        SkillCategory::Talent => "Talent",
+        SkillCategory::Other => "Other",
+    }
+}
+
+impl BattleContext {
+    fn skill_action_value(&self, skill: &SkillHistoryEntry) -> f64 {
+        self.skill_turn(skill)
+            .map_or(0., |(_, action_value, ..)| *action_value)
+    }
+
+    pub fn rotation_report(&self) -> Vec<AvatarRotationReport> {
+        self.avatar_lineup
+            .iter()
+            .map(|avatar| {
+                let mut uses_per_cycle: Vec<SkillUseCounts> = Vec::new();
+                let mut wasted_ultimates = Vec::new();
+                let mut sequences = Vec::new();
+                let mut pending: Option<(Vec<&str>, f64, f64)> = None;
+                for skill in self
+                    .skill_history
+                    .iter()
+                    .filter(|skill| skill.avatar_id == avatar.id)
+                {
+                    let category = skill.category();
+                    let action_value = self.skill_action_value(skill);
+
+                    let cycle = cycle_at(action_value) as usize;
+                    if uses_per_cycle.len() <= cycle {
+                        uses_per_cycle.resize_with(cycle + 1, Default::default);
+                    }
+                    uses_per_cycle[cycle].add(category);
+
+                    if category == SkillCategory::Ultimate
+                        && skill.total_damage <= 0.
+                        && skill.ally_stat_changes == 0
+                    {
+                        wasted_ultimates.push(action_value);
+                    }
+
+                    // Follow-ups aren't chosen by the player so they don't shape the rotation
+                    if !matches!(
+                        category,
+                        SkillCategory::Basic | SkillCategory::Skill | SkillCategory::Ultimate
+                    ) {
+                        continue;
+                    }
+                    let (labels, _, damage) =
+                        pending.get_or_insert_with(|| (Vec::new(), action_value, 0.));
+                    labels.push(category_label(category));
+                    *damage += skill.total_damage;
+                    if category == SkillCategory::Ultimate {
+                        let (labels, action_value, damage) = pending.take().unwrap();
+                        sequences.push(SkillSequence {
+                            sequence: labels.join("-"),
+                            action_value,
+                            damage,
+                        });
+                    }
+                }
+
+                let mut sequence_counts: HashMap<&str, usize> = HashMap::new();
+                for sequence in &sequences {
+                    *sequence_counts.entry(&sequence.sequence).or_default() += 1;
+                }
+                let rotation = sequence_counts
+                    .into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                    .map(|(sequence, _)| sequence.to_string());
+
+                let mut top_sequences = sequences;
+                top_sequences.sort_by(|a, b| b.damage.total_cmp(&a.damage));
+                top_sequences.truncate(TOP_SEQUENCE_COUNT);
+
+                AvatarRotationReport {
+                    avatar_id: avatar.id,
+                    name: avatar.name.clone(),
+                    rotation,
+                    uses_per_cycle,
+                    wasted_ultimates,
+                    top_sequences,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+use crate::battle::{
+    BASE_ACTION_GAUGE, BattleContext,
+    cycles::{cycle_end, cycle_start},
+};
+use crate::models::misc::{Entity, Team};
+
+// Intervals within this much of the expected one are considered unaffected
+const GAUGE_SHIFT_TOLERANCE: f64 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum GaugeShiftKind {
+    Advance,
+    Delay,
+}
+
+// An interval between two turns that doesn't match the avatar's speed
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GaugeShift {
+    pub action_value: f64,
+    pub kind: GaugeShiftKind,
+    // In AV
+    pub amount: f64,
+    // Share of the expected interval, 0 to 1
+    pub ratio: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvatarSpeedReport {
+    pub avatar_id: u32,
+    pub name: String,
+    pub speed: f64,
+    pub actions: u32,
+    pub average_action_interval: Option<f64>,
+    pub expected_action_interval: Option<f64>,
+    pub gauge_shifts: Vec<GaugeShift>,
+    // Index w/ cycle
+    pub actions_per_cycle: Vec<u32>,
+    // Index w/ cycle, from the speed at the start of each cycle
+    pub theoretical_actions_per_cycle: Vec<f64>,
+}
+
+impl AvatarSpeedReport {
+    pub fn count_shifts(&self, kind: GaugeShiftKind) -> usize {
+        self.gauge_shifts
+            .iter()
+            .filter(|shift| shift.kind == kind)
+            .count()
+    }
+}
+
+impl BattleContext {
+    // Speed of an entity at the given AV, falling back on its current speed
+    pub fn speed_at(&self, entity: &Entity, action_value: f64) -> Option<f64> {
+        self.stat_history
+            .iter()
+            .rev()
+            .find(|change| {
+                change.stat == "Speed"
+                    && change.entity == *entity
+                    && change.entity.team == entity.team
+                    && change.action_value <= action_value
+            })
+            .map(|change| change.value)
+            .or_else(|| {
+                self.battle_avatars
+                    .iter()
+                    .chain(&self.battle_enemies)
+                    .find(|battle_entity| battle_entity.entity == *entity)
+                    .map(|battle_entity| battle_entity.battle_stats.speed)
+            })
+            .filter(|speed| *speed > 0.)
+    }
+
+    pub fn speed_report(&self) -> Vec<AvatarSpeedReport> {
+        self.avatar_lineup
+            .iter()
+            .enumerate()
+            .map(|(i, avatar)| {
+                let entity = Entity {
+                    uid: avatar.id,
+                    team: Team::Player,
+                };
+                let turns = self
+                    .entity_turn_history
+                    .iter()
+                    .filter(|(turn_owner, ..)| *turn_owner == entity)
+                    .map(|(_, action_value, ..)| *action_value)
+                    .collect::<Vec<f64>>();
+
+                let mut gauge_shifts = Vec::new();
+                for window in turns.windows(2) {
+                    let (previous, next) = (window[0], window[1]);
+                    let Some(speed) = self.speed_at(&entity, previous) else {
+                        continue;
+                    };
+                    let expected = BASE_ACTION_GAUGE / speed;
+                    let actual = next - previous;
+                    let kind = if actual < expected - GAUGE_SHIFT_TOLERANCE {
+                        GaugeShiftKind::Advance
+                    } else if actual > expected + GAUGE_SHIFT_TOLERANCE {
+                        GaugeShiftKind::Delay
+                    } else {
+                        continue;
+                    };
+                    gauge_shifts.push(GaugeShift {
+                        action_value: previous,
+                        kind,
+                        amount: (expected - actual).abs(),
+                        ratio: (expected - actual).abs() / expected,
+                    });
+                }
+
+                let average_action_interval = (turns.len() > 1).then(|| {
+                    (turns[turns.len() - 1] - turns[0]) / (turns.len() - 1) as f64
+                });
+                let speed = self
+                    .battle_avatars
+                    .get(i)
+                    .map_or(0., |battle_avatar| battle_avatar.battle_stats.speed);
+
+                let actions_per_cycle = self
+                    .cycle_segments
+                    .iter()
+                    .map(|segment| segment.avatars_actions.get(i).copied().unwrap_or(0))
+                    .collect();
+                let theoretical_actions_per_cycle = self
+                    .cycle_segments
+                    .iter()
+                    .map(|segment| {
+                        let cycle_length = cycle_end(segment.cycle) - cycle_start(segment.cycle);
+                        self.speed_at(&entity, segment.start_action_value)
+                            .map_or(0., |speed| cycle_length * speed / BASE_ACTION_GAUGE)
+                    })
+                    .collect();
+
+                AvatarSpeedReport {
+                    avatar_id: avatar.id,
+                    name: avatar.name.clone(),
+                    speed,
+                    actions: turns.len() as u32,
+                    average_action_interval,
+                    expected_action_interval: (speed > 0.).then(|| BASE_ACTION_GAUGE / speed),
+                    gauge_shifts,
+                    actions_per_cycle,
+                    theoretical_actions_per_cycle,
+                }
+            })
+            .collect()
+    }
+}
@@ -1,8 +1,11 @@
+use std::collections::HashMap;
 use std::sync::{LazyLock, Mutex, MutexGuard};
 
-use anyhow::{Context, Result};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    analysis::{attribution::BuffRecord, damage_formula::HitRecord},
     models::{
         events::*,
         misc::*,
@@ -11,6 +14,44 @@ use crate::{
     server,
 };
 
+use actions::OutOfTurnAction;
+use cycles::CycleSegment;
+use downtime::AvatarDeath;
+use enemy_hp::EnemyHpSample;
+use phases::BossPhase;
+use run::EndgameRun;
+use summons::SummonDamage;
+use technique::TechniquePhase;
+use waves::WaveSegment;
+
+pub mod actions;
+pub mod cycles;
+pub mod downtime;
+pub mod enemy_hp;
+pub mod lineup;
+pub mod phases;
+pub mod run;
+pub mod scoring;
+pub mod summons;
+pub mod technique;
+pub mod timeline;
+pub mod waves;
+
+// Cycle 0 lasts 150 AV and every following cycle 100 AV
+pub const FIRST_CYCLE_ACTION_VALUE: f64 = 150.;
+pub const CYCLE_ACTION_VALUE: f64 = 100.;
+// AV an entity needs to act again from the start of its turn, divided by its speed
+pub const BASE_ACTION_GAUGE: f64 = 10000.;
+
+// A cycle includes the AV it ends on, so an action at exactly 150 AV is still in cycle 0
+pub fn cycle_at(action_value: f64) -> u32 {
+    if action_value <= FIRST_CYCLE_ACTION_VALUE {
+        0
+    } else {
+        ((action_value - FIRST_CYCLE_ACTION_VALUE) / CYCLE_ACTION_VALUE).ceil() as u32
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SkillHistoryEntry {
     pub avatar_id: u32,
@@ -19,6 +60,68 @@ pub struct SkillHistoryEntry {
     pub total_damage: f64,
     pub damage_detail: Vec<(f64, isize)>,
     pub turn_battle_id: u32,
+    // Stat changes on allies while this was the last skill used in the turn
+    pub ally_stat_changes: u32,
+    // Follow-ups and other insert skills keep the id of the turn they interrupted
+    pub out_of_turn: bool,
+}
+
+// Skill types are RPG.GameCore.AttackType values
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum SkillCategory {
+    Basic,
+    Skill,
+    Ultimate,
+    // Follow-ups, usually triggered by talents
+    Talent,
+    Other,
+}
+
+impl SkillCategory {
+    pub fn from_skill_type(skill_type: u32) -> Self {
+        match skill_type {
+            1 => Self::Basic,
+            2 => Self::Skill,
+            3 => Self::Ultimate,
+            9 => Self::Talent,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl SkillHistoryEntry {
+    pub fn category(&self) -> SkillCategory {
+        SkillCategory::from_skill_type(self.skill_type)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefeatRecord {
+    pub killer: Entity,
+    pub entity_defeated: Entity,
+    pub action_value: f64,
+    pub wave: u32,
+}
+
+// A stat reported by on_stat_change
+#[derive(Clone, Debug)]
+pub struct StatChange {
+    pub entity: Entity,
+    pub stat: &'static str,
+    pub value: f64,
+    pub turn_battle_id: u32,
+    pub action_value: f64,
+}
+
+// An avatar's damage split by whose turn it was dealt in
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnOwnerDamage {
+    pub avatar_id: u32,
+    pub name: String,
+    pub ally_turn_damage: f64,
+    pub enemy_turn_damage: f64,
 }
 
 #[derive(Clone, Copy)]
@@ -36,11 +139,19 @@ pub enum BattleState {
 #[derive(Default, Clone)]
 pub struct BattleContext {
     pub state: Option<BattleState>,
+    // Starts as the lineup the battle was set up with, avatars joining later are appended
     pub avatar_lineup: Vec<Avatar>,
+    // Avatars currently on the field
+    pub player_formation: Vec<Entity>,
+    pub lineup_changes: Vec<LineupChange>,
     pub battle_avatars: Vec<BattleEntity>,
     pub enemies: Vec<Enemy>,
     pub enemy_lineup: Vec<Entity>,
     pub battle_enemies: Vec<BattleEntity>,
+    // Every HP change of every enemy, starting with its HP when it spawned
+    pub enemy_hp_history: Vec<EnemyHpSample>,
+    // Phases of elites and bosses, in the order they started
+    pub boss_phases: Vec<BossPhase>,
     pub turn_history: Vec<TurnInfo>,
     pub av_history: Vec<TurnInfo>,
     pub entity_turn_history: Vec<(Entity, f64, u32, u32)>,
@@ -63,14 +174,46 @@ pub struct BattleContext {
     pub wave: u32,
     pub cycle: u32,
     pub max_cycle: u32,
+    // Cycles the stage allows, as reported when the battle begins
+    pub cycle_limit: u32,
     pub stage_id: u32,
     pub battle_mode: BattleMode,
+    pub segment_markers: Vec<SegmentMarker>,
+    pub defeat_history: Vec<DefeatRecord>,
+    pub avatar_deaths: Vec<AvatarDeath>,
+    pub wave_segments: Vec<WaveSegment>,
+    // Index w/ cycle, derived from AV rather than the game's cycle updates
+    pub cycle_segments: Vec<CycleSegment>,
+    // Every stat change in the order they were reported
+    pub stat_history: Vec<StatChange>,
+    // Latest value of every stat reported for each entity, keyed by uid and team then stat kind.
+    // Entity equality ignores the team, and an avatar can share its uid with an enemy
+    pub entity_stats: HashMap<(u32, Team), HashMap<&'static str, f64>>,
+    pub buff_history: Vec<BuffRecord>,
+    // Every hit with the stats of its attacker and defender at the time
+    pub hit_history: Vec<HitRecord>,
+    pub summons: Vec<SummonDamage>,
+    pub out_of_turn_actions: Vec<OutOfTurnAction>,
+    // Insert skill whose damage is being tallied, closed by the next skill or turn boundary
+    pub current_out_of_turn_action: Option<OutOfTurnAction>,
+    // Index w/ lineup index
+    // Damage of other avatars' hits credited to each support
+    pub enabled_damages: Vec<f64>,
+    // Index w/ lineup index
+    // Damage of each avatar's hits credited to supports
+    pub enabled_by_others: Vec<f64>,
+    // Index w/ lineup index
+    // Damage dealt while an enemy had the turn, e.g. counters and DoT ticks
+    pub enemy_turn_damages: Vec<f64>,
+    pub technique_phase: TechniquePhase,
+    // Survives across battles so both halves of an endgame node can be grouped
+    pub run: Option<EndgameRun>,
 
     // TODO: Move everything not meant to be exposed in the API here
     // pub internal: BattleContextInternal,
 }
 
-#[derive(Default, Clone, Copy, PartialEq)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum BattleMode {
     MOC,
     PF,
@@ -101,16 +244,17 @@ impl BattleContext {
         CSV_DATA_READY.lock().ok()?.take()
     }
 
-    fn find_lineup_index_by_avatar_id(
-        battle_context: &MutexGuard<'static, Self>,
-        avatar_id: u32,
-    ) -> Option<usize> {
-        let res = battle_context
-            .avatar_lineup
-            .iter()
-            .enumerate()
-            .find(|(_index, avatar)| avatar.id == avatar_id);
-        res.map_or(None, |(index, _)| Some(index))
+    // Latest stats reported for the entity on its own team
+    pub fn stats_of(&self, entity: &Entity) -> Option<&HashMap<&'static str, f64>> {
+        self.entity_stats.get(&(entity.uid, entity.team.clone()))
+    }
+
+    // Turn that was ongoing when a skill was used
+    pub fn skill_turn(&self, skill: &SkillHistoryEntry) -> Option<&(Entity, f64, u32, u32)> {
+        skill
+            .turn_battle_id
+            .checked_sub(1)
+            .and_then(|i| self.entity_turn_history.get(i as usize))
     }
 
     fn initialize_battle_context(battle_context: &mut MutexGuard<'static, Self>) {
@@ -123,6 +267,8 @@ impl BattleContext {
 
         battle_context.enemies = Vec::new();
         battle_context.battle_enemies = Vec::new();
+        battle_context.enemy_hp_history = Vec::new();
+        battle_context.boss_phases = Vec::new();
 
         battle_context.turn_count = 0;
         battle_context.total_damage = 0.;
@@ -130,9 +276,138 @@ impl BattleContext {
         battle_context.action_value = 0.;
         battle_context.max_waves = 0;
         battle_context.max_cycle = 0;
+        battle_context.cycle_limit = 0;
         battle_context.wave = 0;
         battle_context.cycle = 0;
         battle_context.stage_id = 0;
+        battle_context.segment_markers = Vec::new();
+        battle_context.defeat_history = Vec::new();
+        battle_context.avatar_deaths = Vec::new();
+        battle_context.wave_segments = Vec::new();
+        battle_context.cycle_segments = Vec::new();
+        battle_context.stat_history = Vec::new();
+        battle_context.entity_stats = HashMap::new();
+        battle_context.buff_history = Vec::new();
+        battle_context.hit_history = Vec::new();
+        battle_context.summons = Vec::new();
+        battle_context.out_of_turn_actions = Vec::new();
+        battle_context.current_out_of_turn_action = None;
+        battle_context.lineup_changes = Vec::new();
+        battle_context.player_formation = Vec::new();
+    }
+
+    // Starts the tallies over as if the battle began at the current AV. Every history is
+    // cleared, only where the battle stands carries over: the lineup, enemies still alive,
+    // AV, wave, cycle, latest stats, active buffs, ongoing boss phases and downed avatars
+    pub fn reset_tallies(&mut self) {
+        let previous = std::mem::take(self);
+        let lineup_len = previous.avatar_lineup.len();
+        let action_value = previous.action_value;
+
+        // Enemies still alive and bosses mid-phase are tracked again from now on
+        let enemy_hp_history = previous
+            .enemies
+            .iter()
+            .filter(|enemy| {
+                !previous.defeat_history.iter().any(|defeat| {
+                    defeat.entity_defeated.team == Team::Enemy
+                        && defeat.entity_defeated.uid == enemy.uid
+                })
+            })
+            .filter_map(|enemy| {
+                let hp = previous
+                    .entity_stats
+                    .get(&(enemy.uid, Team::Enemy))
+                    .and_then(|stats| stats.get(Stat::HP(0.).kind()))?;
+                Some(EnemyHpSample {
+                    uid: enemy.uid,
+                    hp: *hp,
+                    action_value,
+                    turn_battle_id: 0,
+                })
+            })
+            .collect();
+        let boss_phases = previous
+            .boss_phases
+            .iter()
+            .filter(|phase| phase.end_action_value.is_none())
+            .map(|phase| BossPhase {
+                damage: 0.,
+                start_action_value: action_value,
+                ..phase.clone()
+            })
+            .collect();
+        // Avatars that are still down start their downtime over
+        let avatar_deaths = previous
+            .avatar_deaths
+            .iter()
+            .filter(|death| death.revived_action_value.is_none())
+            .map(|death| AvatarDeath {
+                action_value,
+                turn_count: 0,
+                ..death.clone()
+            })
+            .collect();
+        let buff_history = previous
+            .buff_history
+            .into_iter()
+            .filter(|buff| buff.expired_action_value.is_none())
+            .collect();
+
+        *self = Self {
+            state: previous.state,
+            avatar_lineup: previous.avatar_lineup,
+            player_formation: previous.player_formation,
+            battle_avatars: previous.battle_avatars,
+            enemies: previous.enemies,
+            enemy_lineup: previous.enemy_lineup,
+            battle_enemies: previous.battle_enemies,
+            enemy_hp_history,
+            boss_phases,
+            avatar_deaths,
+            buff_history,
+            // Kept so the reset isn't mistaken for the technique phase
+            current_turn_battle_id: previous.current_turn_battle_id,
+            last_wave_action_value: previous.last_wave_action_value,
+            action_value,
+            current_turn_info: TurnInfo {
+                avatars_turn_damage: vec![0f64; lineup_len],
+                total_damage: 0.,
+                ..previous.current_turn_info
+            },
+            max_waves: previous.max_waves,
+            wave: previous.wave,
+            cycle: previous.cycle,
+            max_cycle: previous.max_cycle,
+            cycle_limit: previous.cycle_limit,
+            stage_id: previous.stage_id,
+            battle_mode: previous.battle_mode,
+            entity_stats: previous.entity_stats,
+            run: previous.run,
+            real_time_damages: vec![0f64; lineup_len],
+            real_time_overkill_damages: vec![0f64; lineup_len],
+            enabled_damages: vec![0f64; lineup_len],
+            enabled_by_others: vec![0f64; lineup_len],
+            enemy_turn_damages: vec![0f64; lineup_len],
+            technique_phase: TechniquePhase {
+                avatars_damage: vec![0f64; lineup_len],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        self.current_wave_segment_mut();
+    }
+
+    pub fn mark_segment(&mut self, label: String) -> SegmentMarker {
+        let segment = SegmentMarker {
+            label,
+            action_value: self.action_value,
+            turn_count: self.turn_count,
+            avatars_damage: self.real_time_damages.clone(),
+            total_damage: self.total_damage,
+        };
+        self.segment_markers.push(segment.clone());
+        segment
     }
 
     fn get_battle_mode(stage_id: u32) -> BattleMode {
@@ -158,8 +433,11 @@ impl BattleContext {
         log::info!("Battle has started");
         log::info!("Max Waves: {}", e.max_waves);
         battle_context.max_waves = e.max_waves;
+        battle_context.cycle_limit = e.max_cycles;
+        battle_context.stage_id = e.stage_id;
 
         battle_context.battle_mode = BattleContext::get_battle_mode(e.stage_id);
+        battle_context.enter_run(e.stage_id);
 
         Ok(Packet::OnBattleBegin {
             max_waves: e.max_waves,
@@ -177,6 +455,13 @@ impl BattleContext {
         battle_context.current_turn_info.avatars_turn_damage = vec![0f64; e.avatars.len()];
         battle_context.real_time_damages = vec![0f64; e.avatars.len()];
         battle_context.real_time_overkill_damages = vec![0f64; e.avatars.len()];
+        battle_context.enabled_damages = vec![0f64; e.avatars.len()];
+        battle_context.enabled_by_others = vec![0f64; e.avatars.len()];
+        battle_context.enemy_turn_damages = vec![0f64; e.avatars.len()];
+        battle_context.technique_phase = TechniquePhase {
+            avatars_damage: vec![0f64; e.avatars.len()],
+            ..Default::default()
+        };
         battle_context.avatar_lineup = e.avatars;
 
         let mut battle_avatars = Vec::new();
@@ -190,6 +475,11 @@ impl BattleContext {
             });
         }
         battle_context.battle_avatars = battle_avatars;
+        battle_context.player_formation = battle_context
+            .battle_avatars
+            .iter()
+            .map(|avatar| avatar.entity.clone())
+            .collect();
 
         for avatar in &battle_context.avatar_lineup {
             log::info!("{} was loaded in lineup", avatar);
@@ -204,15 +494,50 @@ impl BattleContext {
         e: OnDamageEvent,
         mut battle_context: MutexGuard<'static, BattleContext>,
     ) -> Result<Packet> {
-        let lineup_index = Self::find_lineup_index_by_avatar_id(&battle_context, e.attacker.uid)
-            .with_context(|| format!("Could not find avatar {} in lineup", e.attacker.uid))?;
-        let turn = &mut battle_context.current_turn_info;
+        // Avatars missing from the lineup joined mid-battle, e.g. trial or support characters
+        let lineup_index = battle_context.lineup_index_or_register(&e.avatar);
         // Record character damage chunk
-        turn.avatars_turn_damage[lineup_index] += e.damage;
+        // Openers land before anyone takes a turn, so they're kept out of the first turn
+        if battle_context.in_technique_phase() {
+            battle_context.record_technique_damage(lineup_index, e.damage, e.damage_type);
+        } else {
+            battle_context.current_turn_info.avatars_turn_damage[lineup_index] += e.damage;
+            if let Some(action) = battle_context.current_out_of_turn_action.as_mut() {
+                action.avatars_damage[lineup_index] += e.damage;
+                action.total_damage += e.damage;
+            }
+        }
         battle_context.real_time_damages[lineup_index] += e.damage as f64;
+        if battle_context.is_enemy_turn() {
+            battle_context.enemy_turn_damages[lineup_index] += e.damage;
+        }
         battle_context.real_time_overkill_damages[lineup_index] += e.overkill_damage as f64;
         battle_context.total_damage += e.damage as f64;
 
+        let wave_segment = battle_context.current_wave_segment_mut();
+        wave_segment.avatars_damage[lineup_index] += e.damage;
+        wave_segment.total_damage += e.damage;
+
+        let action_value = battle_context.action_value;
+        let cycle_segment = battle_context.cycle_segment_mut(action_value);
+        cycle_segment.avatars_damage[lineup_index] += e.damage;
+        cycle_segment.total_damage += e.damage;
+
+        battle_context.attribute_hit(&e.attacker, e.defender.as_ref(), e.damage);
+        battle_context.record_hit(
+            &e.attacker,
+            e.defender.as_ref(),
+            e.damage,
+            e.damage_type,
+            e.element,
+        );
+        if let Some(defender) = &e.defender {
+            battle_context.record_boss_damage(defender, e.damage);
+        }
+        if let Some(summon) = &e.summon {
+            battle_context.record_summon_damage(e.attacker.uid, summon, e.damage);
+        }
+
         if let Some(last_skill) = battle_context.skill_history.iter_mut().rev().find(|skill| skill.avatar_id == e.attacker.uid) {
             last_skill.damage_detail.push((e.damage as f64, e.damage_type as isize));
             last_skill.total_damage += e.damage as f64;
@@ -220,6 +545,8 @@ impl BattleContext {
 
         Ok(Packet::OnDamage {
             attacker: e.attacker,
+            defender: e.defender,
+            summon: e.summon,
             damage: e.damage,
             damage_type: e.damage_type,
         })
@@ -229,12 +556,32 @@ impl BattleContext {
         e: OnTurnBeginEvent,
         mut battle_context: MutexGuard<'static, BattleContext>,
     ) -> Result<Packet> {
+        battle_context.close_out_of_turn_action();
         battle_context.action_value = e.action_value;
         battle_context.current_turn_info.action_value = e.action_value;
+        battle_context.current_turn_info.turn_owner = e.turn_owner.clone();
 
         battle_context.current_turn_battle_id += 1;
 
         if let Some(turn_owner) = &e.turn_owner {
+            battle_context
+                .current_wave_segment_mut()
+                .first_action
+                .get_or_insert_with(|| turn_owner.clone());
+
+            if turn_owner.team == Team::Player {
+                // Downed avatars don't take turns
+                battle_context.record_avatar_revive(turn_owner.uid);
+            }
+            if let Some(lineup_index) = battle_context
+                .lineup_index(turn_owner.uid)
+                .filter(|_| turn_owner.team == Team::Player)
+            {
+                battle_context
+                    .cycle_segment_mut(e.action_value)
+                    .avatars_actions[lineup_index] += 1;
+            }
+
             let wave = battle_context.wave;
             let cycle = battle_context.cycle;
             battle_context.entity_turn_history.push((
@@ -256,6 +603,7 @@ impl BattleContext {
     fn handle_on_turn_end_event(
         mut battle_context: MutexGuard<'static, BattleContext>,
     ) -> Result<Packet> {
+        battle_context.close_out_of_turn_action();
         battle_context.current_turn_info.wave = battle_context.wave;
         battle_context.current_turn_info.cycle = battle_context.cycle;
 
@@ -267,20 +615,7 @@ impl BattleContext {
         } else {
             turn_info.avatars_turn_damage.iter().sum()
         };
-        battle_context.turn_history.push(turn_info.clone());
-
-        // If same AV, update damage
-        if let Some(last_turn) = battle_context.av_history.last_mut() {
-            if last_turn.action_value == turn_info.action_value {
-                for (i, incoming_dmg) in turn_info.avatars_turn_damage.iter().enumerate() {
-                    last_turn.avatars_turn_damage[i] += incoming_dmg;
-                }
-            } else {
-                battle_context.av_history.push(turn_info.clone());
-            }
-        } else {
-            battle_context.av_history.push(turn_info.clone());
-        }
+        battle_context.push_turn_info(turn_info.clone());
 
         // Logging
         for (i, avatar) in battle_context.avatar_lineup.iter().enumerate() {
@@ -304,16 +639,77 @@ impl BattleContext {
         // battle_context.current_turn_info.total_damage = 0.0;
         battle_context.current_turn_info.avatars_turn_damage =
             vec![0f64; battle_context.avatar_lineup.len()];
+        // Damage between turns, e.g. ultimates, isn't tied to the last turn's owner
+        battle_context.current_turn_info.turn_owner = None;
         battle_context.turn_count += 1;
+        battle_context.current_wave_segment_mut().turn_count += 1;
 
         Ok(Packet::OnTurnEnd { turn_info })
     }
 
+    pub fn is_enemy_turn(&self) -> bool {
+        self.current_turn_info
+            .turn_owner
+            .as_ref()
+            .is_some_and(|turn_owner| turn_owner.team == Team::Enemy)
+    }
+
+    pub fn turn_owner_damage(&self) -> Vec<TurnOwnerDamage> {
+        self.avatar_lineup
+            .iter()
+            .enumerate()
+            .map(|(i, avatar)| {
+                let damage = self.real_time_damages.get(i).copied().unwrap_or_default();
+                let enemy_turn_damage = self.enemy_turn_damages.get(i).copied().unwrap_or_default();
+                TurnOwnerDamage {
+                    avatar_id: avatar.id,
+                    name: avatar.name.clone(),
+                    ally_turn_damage: damage - enemy_turn_damage,
+                    enemy_turn_damage,
+                }
+            })
+            .collect()
+    }
+
+    fn push_turn_info(&mut self, turn_info: TurnInfo) {
+        self.turn_history.push(turn_info.clone());
+
+        // If same AV, update damage
+        if let Some(last_turn) = self.av_history.last_mut() {
+            if last_turn.action_value == turn_info.action_value {
+                for (i, incoming_dmg) in turn_info.avatars_turn_damage.iter().enumerate() {
+                    last_turn.avatars_turn_damage[i] += incoming_dmg;
+                }
+            } else {
+                self.av_history.push(turn_info);
+            }
+        } else {
+            self.av_history.push(turn_info);
+        }
+    }
+
     fn handle_on_entity_defeated_event(
         e: OnEntityDefeatedEvent,
-        mut _battle_context: MutexGuard<'static, BattleContext>,
+        mut battle_context: MutexGuard<'static, BattleContext>,
     ) -> Result<Packet> {
         // log::info!("{} has defeated {}", e.attacker);
+        let defeat = DefeatRecord {
+            killer: e.killer.clone(),
+            entity_defeated: e.entity_defeated.clone(),
+            action_value: battle_context.action_value,
+            wave: battle_context.wave,
+        };
+        battle_context.defeat_history.push(defeat);
+        match e.entity_defeated.team {
+            Team::Player => {
+                log::info!("Avatar {} went down", e.entity_defeated.uid);
+                battle_context.record_avatar_death(e.entity_defeated.uid, &e.killer);
+            }
+            Team::Enemy => {
+                battle_context.current_wave_segment_mut().kills += 1;
+                battle_context.end_boss_phase(e.entity_defeated.uid);
+            }
+        }
 
         Ok(Packet::OnEntityDefeated {
             killer: e.killer,
@@ -325,7 +721,10 @@ impl BattleContext {
         mut battle_context: MutexGuard<'static, BattleContext>,
     ) -> Result<Packet> {
         battle_context.state = Some(BattleState::Ended);
-        
+        battle_context.close_out_of_turn_action();
+        battle_context.close_wave_segment();
+        battle_context.record_run_half();
+
         let exporter = crate::export::BattleDataExporter::new();
         
         match std::panic::catch_unwind(|| {
@@ -364,6 +763,11 @@ impl BattleContext {
         e: OnUseSkillEvent,
         mut battle_context: MutexGuard<'static, BattleContext>,
     ) -> Result<Packet> {
+        if e.insert {
+            battle_context.open_out_of_turn_action(&e.avatar, &e.skill);
+        } else {
+            battle_context.close_out_of_turn_action();
+        }
         let turn_battle_id = battle_context.entity_turn_history.len() as u32;
         
         battle_context.skill_history.push(SkillHistoryEntry {
@@ -373,6 +777,8 @@ impl BattleContext {
             total_damage: 0.0,
             damage_detail: Vec::new(),
             turn_battle_id,
+            ally_stat_changes: 0,
+            out_of_turn: e.insert,
         });
 
         Ok(Packet::OnUseSkill {
@@ -392,6 +798,8 @@ impl BattleContext {
         }
 
         battle_context.wave = e.wave;
+        // Open the new segment right away so it starts at the wave transition
+        battle_context.current_wave_segment_mut();
         Ok(Packet::OnUpdateWave { wave: e.wave })
     }
 
@@ -412,6 +820,44 @@ impl BattleContext {
         e: OnStatChangeEvent,
         mut battle_context: MutexGuard<'static, BattleContext>,
     ) -> Result<Packet> {
+        if e.entity.team == Team::Player {
+            let turn_battle_id = battle_context.entity_turn_history.len() as u32;
+            if let Some(last_skill) = battle_context
+                .skill_history
+                .last_mut()
+                .filter(|skill| skill.turn_battle_id == turn_battle_id)
+            {
+                last_skill.ally_stat_changes += 1;
+            }
+
+            if matches!(e.stat, Stat::HP(hp) if hp > 0.) {
+                battle_context.record_avatar_revive(e.entity.uid);
+            }
+        }
+
+        battle_context.track_buff(&e.entity, &e.stat);
+        if e.entity.team == Team::Enemy {
+            battle_context.track_boss_stat(&e.entity, &e.stat);
+        }
+        battle_context
+            .entity_stats
+            .entry((e.entity.uid, e.entity.team.clone()))
+            .or_default()
+            .insert(e.stat.kind(), e.stat.value());
+
+        let change = StatChange {
+            entity: e.entity.clone(),
+            stat: e.stat.kind(),
+            value: e.stat.value(),
+            turn_battle_id: battle_context.entity_turn_history.len() as u32,
+            action_value: battle_context.action_value,
+        };
+        battle_context.stat_history.push(change);
+
+        if let (Team::Enemy, Stat::HP(hp)) = (&e.entity.team, &e.stat) {
+            battle_context.record_enemy_hp(e.entity.uid, *hp);
+        }
+
         match e.entity.team {
             Team::Player => {
                 if let Some(avatar) = battle_context
@@ -461,18 +907,27 @@ impl BattleContext {
         e: OnInitializeEnemyEvent,
         mut battle_context: MutexGuard<'static, BattleContext>,
     ) -> Result<Packet> {
-        battle_context.enemies.push(e.enemy.clone());
+        let mut enemy = e.enemy;
+        enemy.wave = battle_context.wave;
+        enemy.position = battle_context
+            .enemies
+            .iter()
+            .filter(|other| other.wave == enemy.wave)
+            .count() as u32;
+        battle_context.enemies.push(enemy.clone());
+        battle_context.record_enemy_hp(enemy.uid, enemy.base_stats.hp);
+        battle_context.track_boss_spawn(&enemy);
         battle_context.battle_enemies.push(BattleEntity {
             entity: Entity {
-                uid: e.enemy.uid,
+                uid: enemy.uid,
                 team: Team::Enemy,
             },
             battle_stats: BattleStats {
-                hp: e.enemy.base_stats.hp,
+                hp: enemy.base_stats.hp,
                 ..Default::default()
             },
         });
-        Ok(Packet::OnInitializeEnemy { enemy: e.enemy })
+        Ok(Packet::OnInitializeEnemy { enemy })
     }
 
     fn handle_on_update_team_formation_event(
@@ -480,7 +935,9 @@ impl BattleContext {
         mut battle_context: MutexGuard<'static, BattleContext>,
     ) -> Result<Packet> {
         match e.team {
-            Team::Player => {}
+            Team::Player => {
+                battle_context.update_player_formation(&e.avatars);
+            }
             Team::Enemy => {
                 battle_context.enemy_lineup = e.entities.clone();
             }
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+use super::BattleContext;
+use crate::models::misc::{Entity, Skill};
+
+// A follow-up, counter or other insert skill used outside its owner's turn
+// Its damage also counts towards the turn it interrupted, it isn't a turn of its own
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutOfTurnAction {
+    pub entity: Entity,
+    pub skill_name: String,
+    pub skill_type: u32,
+    // Turn that was ongoing when the action triggered
+    pub parent_turn_battle_id: u32,
+    // Owner of that turn
+    pub trigger: Option<Entity>,
+    pub action_value: f64,
+    pub wave: u32,
+    pub cycle: u32,
+    // Index w/ lineup index
+    pub avatars_damage: Vec<f64>,
+    pub total_damage: f64,
+}
+
+impl BattleContext {
+    // Damage dealt until the action is closed is tallied for it as well as the ongoing turn
+    pub(super) fn open_out_of_turn_action(&mut self, entity: &Entity, skill: &Skill) {
+        self.close_out_of_turn_action();
+        self.current_out_of_turn_action = Some(OutOfTurnAction {
+            entity: entity.clone(),
+            skill_name: skill.name.clone(),
+            skill_type: skill.skill_type as u32,
+            parent_turn_battle_id: self.entity_turn_history.len() as u32,
+            trigger: self
+                .entity_turn_history
+                .last()
+                .map(|(turn_owner, ..)| turn_owner.clone()),
+            action_value: self.action_value,
+            wave: self.wave,
+            cycle: self.cycle,
+            avatars_damage: vec![0f64; self.avatar_lineup.len()],
+            total_damage: 0.,
+        });
+    }
+
+    pub(super) fn close_out_of_turn_action(&mut self) {
+        if let Some(action) = self.current_out_of_turn_action.take() {
+            self.out_of_turn_actions.push(action);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use super::{BattleContext, CYCLE_ACTION_VALUE, FIRST_CYCLE_ACTION_VALUE, cycle_at};
+
+// AV at which a cycle starts
+pub fn cycle_start(cycle: u32) -> f64 {
+    if cycle == 0 {
+        0.
+    } else {
+        FIRST_CYCLE_ACTION_VALUE + (cycle - 1) as f64 * CYCLE_ACTION_VALUE
+    }
+}
+
+pub fn cycle_end(cycle: u32) -> f64 {
+    cycle_start(cycle + 1)
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CycleSegment {
+    pub cycle: u32,
+    pub start_action_value: f64,
+    pub end_action_value: f64,
+    // Index w/ lineup index
+    pub avatars_damage: Vec<f64>,
+    // Index w/ lineup index
+    pub avatars_actions: Vec<u32>,
+    pub total_damage: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct CycleProjection {
+    // Only the enemies currently on the field are known
+    pub remaining_enemy_hp: f64,
+    pub projected_clear_action_value: f64,
+    // Cycles still needed after the current one, ignoring later waves
+    pub cycles_remaining: u32,
+    pub on_track_for_zero_cycle: bool,
+}
+
+impl BattleContext {
+    // Segment of the cycle the given AV falls in, creating it and any skipped cycle before it
+    pub(super) fn cycle_segment_mut(&mut self, action_value: f64) -> &mut CycleSegment {
+        let cycle = cycle_at(action_value);
+        while self.cycle_segments.len() <= cycle as usize {
+            let next_cycle = self.cycle_segments.len() as u32;
+            self.cycle_segments.push(CycleSegment {
+                cycle: next_cycle,
+                start_action_value: cycle_start(next_cycle),
+                end_action_value: cycle_end(next_cycle),
+                avatars_damage: vec![0f64; self.avatar_lineup.len()],
+                avatars_actions: vec![0; self.avatar_lineup.len()],
+                total_damage: 0.,
+            });
+        }
+        &mut self.cycle_segments[cycle as usize]
+    }
+
+    pub fn cycle_projection(&self) -> CycleProjection {
+        let remaining_enemy_hp = self
+            .battle_enemies
+            .iter()
+            .map(|enemy| enemy.battle_stats.hp.max(0.))
+            .sum::<f64>();
+        let dpav = if self.action_value > 0. {
+            self.total_damage / self.action_value
+        } else {
+            0.
+        };
+        let projected_clear_action_value = if remaining_enemy_hp <= 0. {
+            self.action_value
+        } else if dpav > 0. {
+            self.action_value + remaining_enemy_hp / dpav
+        } else {
+            f64::INFINITY
+        };
+        let projected_cycle = if projected_clear_action_value.is_finite() {
+            cycle_at(projected_clear_action_value)
+        } else {
+            u32::MAX
+        };
+
+        CycleProjection {
+            remaining_enemy_hp,
+            projected_clear_action_value,
+            cycles_remaining: projected_cycle.saturating_sub(cycle_at(self.action_value)),
+            on_track_for_zero_cycle: projected_cycle == 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::misc::{BattleEntity, BattleStats, Entity, Team};
+
+    fn projection(action_value: f64, total_damage: f64, enemy_hp: f64) -> CycleProjection {
+        let battle_context = BattleContext {
+            action_value,
+            total_damage,
+            battle_enemies: vec![BattleEntity {
+                entity: Entity {
+                    uid: 7,
+                    team: Team::Enemy,
+                },
+                battle_stats: BattleStats {
+                    hp: enemy_hp,
+                    ..Default::default()
+                },
+            }],
+            ..Default::default()
+        };
+        battle_context.cycle_projection()
+    }
+
+    #[test]
+    fn cycles_end_on_their_last_av() {
+        assert_eq!(cycle_at(0.), 0);
+        assert_eq!(cycle_at(150.), 0);
+        assert_eq!(cycle_at(150.0001), 1);
+        assert_eq!(cycle_at(250.), 1);
+        assert_eq!(cycle_at(250.0001), 2);
+        assert_eq!((cycle_start(1), cycle_end(1)), (150., 250.));
+    }
+
+    #[test]
+    fn projects_clear_at_current_damage_per_av() {
+        // 10 damage per AV clears the last 500 HP right at the end of cycle 0
+        let on_track = projection(100., 1000., 500.);
+        assert_eq!(on_track.projected_clear_action_value, 150.);
+        assert!(on_track.on_track_for_zero_cycle);
+        assert_eq!(on_track.cycles_remaining, 0);
+
+        let late = projection(100., 1000., 1500.);
+        assert_eq!(late.projected_clear_action_value, 250.);
+        assert!(!late.on_track_for_zero_cycle);
+        assert_eq!(late.cycles_remaining, 1);
+
+        let cleared = projection(250., 1000., 0.);
+        assert_eq!(cleared.projected_clear_action_value, 250.);
+        assert_eq!(cleared.cycles_remaining, 0);
+    }
+
+    #[test]
+    fn no_damage_yet_is_never_on_track() {
+        let projection = projection(0., 0., 500.);
+        assert!(projection.projected_clear_action_value.is_infinite());
+        assert!(!projection.on_track_for_zero_cycle);
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+use super::BattleContext;
+use crate::models::misc::Entity;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvatarDeath {
+    pub avatar_id: u32,
+    pub killer: Entity,
+    pub action_value: f64,
+    pub wave: u32,
+    // Turns completed when the avatar went down
+    pub turn_count: usize,
+    // None while the avatar is still down
+    pub revived_action_value: Option<f64>,
+    pub revived_turn_count: Option<usize>,
+}
+
+impl AvatarDeath {
+    // AV spent down, up to `action_value` if the avatar wasn't revived
+    pub fn downtime(&self, action_value: f64) -> f64 {
+        (self.revived_action_value.unwrap_or(action_value) - self.action_value).max(0.)
+    }
+}
+
+impl BattleContext {
+    pub fn is_down(&self, avatar_id: u32) -> bool {
+        self.avatar_deaths
+            .iter()
+            .any(|death| death.avatar_id == avatar_id && death.revived_action_value.is_none())
+    }
+
+    pub fn deaths_of(&self, avatar_id: u32) -> impl Iterator<Item = &AvatarDeath> {
+        self.avatar_deaths
+            .iter()
+            .filter(move |death| death.avatar_id == avatar_id)
+    }
+
+    pub fn total_downtime(&self, avatar_id: u32) -> f64 {
+        self.deaths_of(avatar_id)
+            .map(|death| death.downtime(self.action_value))
+            .sum()
+    }
+
+    pub(super) fn record_avatar_death(&mut self, avatar_id: u32, killer: &Entity) {
+        if self.is_down(avatar_id) {
+            return;
+        }
+        self.avatar_deaths.push(AvatarDeath {
+            avatar_id,
+            killer: killer.clone(),
+            action_value: self.action_value,
+            wave: self.wave,
+            turn_count: self.turn_count,
+            revived_action_value: None,
+            revived_turn_count: None,
+        });
+    }
+
+    // Closes the avatar's downtime if it's down, e.g. when it regains HP or takes a turn again
+    pub(super) fn record_avatar_revive(&mut self, avatar_id: u32) {
+        let action_value = self.action_value;
+        let turn_count = self.turn_count;
+        if let Some(death) = self
+            .avatar_deaths
+            .iter_mut()
+            .find(|death| death.avatar_id == avatar_id && death.revived_action_value.is_none())
+        {
+            log::info!("Avatar {} was revived at {:.2} AV", avatar_id, action_value);
+            death.revived_action_value = Some(action_value);
+            death.revived_turn_count = Some(turn_count);
+        }
+    }
+}
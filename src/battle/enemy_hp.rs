@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use super::BattleContext;
+use crate::models::misc::Team;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnemyHpSample {
+    pub uid: u32,
+    pub hp: f64,
+    pub action_value: f64,
+    pub turn_battle_id: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnemyTimeToKill {
+    pub uid: u32,
+    pub name: String,
+    pub wave: u32,
+    pub spawn_action_value: f64,
+    // None if the enemy survived the battle
+    pub defeat_action_value: Option<f64>,
+    pub time_to_kill: Option<f64>,
+    // Sum of every HP drop, so heals and refills don't hide damage
+    pub damage_taken: f64,
+}
+
+impl BattleContext {
+    // The first sample of an enemy is taken when it spawns
+    pub(super) fn record_enemy_hp(&mut self, uid: u32, hp: f64) {
+        let sample = EnemyHpSample {
+            uid,
+            hp,
+            action_value: self.action_value,
+            turn_battle_id: self.entity_turn_history.len() as u32,
+        };
+        self.enemy_hp_history.push(sample);
+    }
+
+    pub fn hp_samples_of(&self, uid: u32) -> impl Iterator<Item = &EnemyHpSample> {
+        self.enemy_hp_history
+            .iter()
+            .filter(move |sample| sample.uid == uid)
+    }
+
+    pub fn enemy_time_to_kill(&self) -> Vec<EnemyTimeToKill> {
+        self.enemies
+            .iter()
+            .map(|enemy| {
+                let spawn_action_value = self
+                    .hp_samples_of(enemy.uid)
+                    .next()
+                    .map_or(0., |sample| sample.action_value);
+                let defeat_action_value = self
+                    .defeat_history
+                    .iter()
+                    .find(|defeat| {
+                        defeat.entity_defeated.team == Team::Enemy
+                            && defeat.entity_defeated.uid == enemy.uid
+                    })
+                    .map(|defeat| defeat.action_value);
+                let damage_taken = self
+                    .hp_samples_of(enemy.uid)
+                    .zip(self.hp_samples_of(enemy.uid).skip(1))
+                    .map(|(previous, sample)| (previous.hp - sample.hp).max(0.))
+                    .sum();
+
+                EnemyTimeToKill {
+                    uid: enemy.uid,
+                    name: enemy.name.clone(),
+                    wave: enemy.wave,
+                    spawn_action_value,
+                    defeat_action_value,
+                    time_to_kill: defeat_action_value
+                        .map(|action_value| action_value - spawn_action_value),
+                    damage_taken,
+                }
+            })
+            .collect()
+    }
+}
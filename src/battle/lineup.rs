@@ -0,0 +1,142 @@
+use super::BattleContext;
+use crate::{
+    models::{
+        misc::{Avatar, BattleEntity, BattleStats, Entity, LineupChange, LineupChangeKind, Team},
+        packets::Packet,
+    },
+    server,
+};
+
+impl BattleContext {
+    pub fn lineup_index(&self, avatar_id: u32) -> Option<usize> {
+        self.avatar_lineup
+            .iter()
+            .position(|avatar| avatar.id == avatar_id)
+    }
+
+    fn on_field(&self, avatar_id: u32) -> bool {
+        self.player_formation
+            .iter()
+            .any(|entity| entity.uid == avatar_id)
+    }
+
+    // Lineup index of the avatar, registering it first if it joined after the lineup was set
+    pub(super) fn lineup_index_or_register(&mut self, avatar: &Avatar) -> usize {
+        self.lineup_index(avatar.id)
+            .unwrap_or_else(|| self.register_avatar(avatar.clone()))
+    }
+
+    // Appends the avatar to the lineup and grows every tally indexed by lineup index,
+    // including the ones already recorded, so they all keep the lineup's length
+    fn register_avatar(&mut self, avatar: Avatar) -> usize {
+        let lineup_index = self.avatar_lineup.len();
+        let lineup_len = lineup_index + 1;
+        log::info!("{} joined the lineup", avatar);
+
+        self.battle_avatars.push(BattleEntity {
+            entity: Entity {
+                uid: avatar.id,
+                team: Team::Player,
+            },
+            battle_stats: BattleStats::default(),
+        });
+        self.avatar_lineup.push(avatar.clone());
+        // Avatars only join by taking the field
+        if !self.player_formation.is_empty() {
+            self.player_formation.push(Entity {
+                uid: avatar.id,
+                team: Team::Player,
+            });
+        }
+
+        for tally in [
+            &mut self.current_turn_info.avatars_turn_damage,
+            &mut self.real_time_damages,
+            &mut self.real_time_overkill_damages,
+            &mut self.enabled_damages,
+            &mut self.enabled_by_others,
+            &mut self.enemy_turn_damages,
+            &mut self.technique_phase.avatars_damage,
+        ] {
+            tally.resize(lineup_len, 0.);
+        }
+        for turn in self
+            .turn_history
+            .iter_mut()
+            .chain(self.av_history.iter_mut())
+        {
+            turn.avatars_turn_damage.resize(lineup_len, 0.);
+        }
+        for segment in &mut self.wave_segments {
+            segment.avatars_damage.resize(lineup_len, 0.);
+        }
+        for segment in &mut self.cycle_segments {
+            segment.avatars_damage.resize(lineup_len, 0.);
+            segment.avatars_actions.resize(lineup_len, 0);
+        }
+        for marker in &mut self.segment_markers {
+            marker.avatars_damage.resize(lineup_len, 0.);
+        }
+        if let Some(action) = self.current_out_of_turn_action.as_mut() {
+            action.avatars_damage.resize(lineup_len, 0.);
+        }
+
+        self.record_lineup_change(&avatar, LineupChangeKind::Joined);
+        lineup_index
+    }
+
+    // Diffs the player formation against the previous one
+    pub(super) fn update_player_formation(&mut self, avatars: &[Avatar]) {
+        // The formation is emptied while the battle is torn down
+        if avatars.is_empty() {
+            return;
+        }
+        // The first formation of the battle is the starting one, nobody returned to it
+        let first_formation = self.player_formation.is_empty();
+        for avatar in avatars {
+            if self.lineup_index(avatar.id).is_none() {
+                self.register_avatar(avatar.clone());
+            } else if !first_formation && !self.on_field(avatar.id) {
+                self.record_lineup_change(avatar, LineupChangeKind::Returned);
+                self.record_avatar_revive(avatar.id);
+            }
+        }
+
+        let left = self
+            .avatar_lineup
+            .iter()
+            .filter(|avatar| {
+                self.on_field(avatar.id) && !avatars.iter().any(|other| other.id == avatar.id)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for avatar in &left {
+            self.record_lineup_change(avatar, LineupChangeKind::Left);
+        }
+
+        self.player_formation = avatars
+            .iter()
+            .map(|avatar| Entity {
+                uid: avatar.id,
+                team: Team::Player,
+            })
+            .collect();
+    }
+
+    fn record_lineup_change(&mut self, avatar: &Avatar, kind: LineupChangeKind) {
+        let change = LineupChange {
+            avatar_id: avatar.id,
+            name: avatar.name.clone(),
+            kind,
+            action_value: self.action_value,
+            wave: self.wave,
+            turn_battle_id: self.entity_turn_history.len() as u32,
+        };
+        self.lineup_changes.push(change.clone());
+        // Sent right away so clients know the avatar before any damage it deals
+        server::broadcast(Packet::OnLineupChange {
+            change,
+            avatars: self.avatar_lineup.clone(),
+        });
+    }
+}
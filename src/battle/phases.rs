@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+use super::BattleContext;
+use crate::models::misc::{Enemy, EnemyRank, Entity, Stat};
+
+// MaxHP changes smaller than this are rounding, not a new phase
+const MAX_HP_EPSILON: f64 = 1.;
+// AV is accumulated from floats, so a replacement's spawn AV can drift slightly from
+// the AV its predecessor was defeated at
+const REPLACEMENT_AV_EPSILON: f64 = 0.01;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PhaseTrigger {
+    Spawn,
+    MaxHp,
+    RallyHp,
+    NegativeHp,
+    // A new entity with the same monster id took over when the previous one was defeated
+    Replacement,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BossPhase {
+    // Uid of the entity the boss spawned as, shared by all of its phases
+    pub boss_uid: u32,
+    pub uid: u32,
+    pub monster_id: u32,
+    pub name: String,
+    // Starts at 1
+    pub phase: u32,
+    pub trigger: PhaseTrigger,
+    pub max_hp: f64,
+    pub damage: f64,
+    pub wave: u32,
+    pub start_action_value: f64,
+    // None while the phase is ongoing
+    pub end_action_value: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BossPhaseSummary {
+    pub boss_uid: u32,
+    pub monster_id: u32,
+    pub name: String,
+    pub phases: u32,
+    // HP of every phase added up
+    pub total_hp: f64,
+    pub total_damage: f64,
+}
+
+impl BattleContext {
+    fn current_phase_mut(&mut self, uid: u32) -> Option<&mut BossPhase> {
+        self.boss_phases
+            .iter_mut()
+            .rev()
+            .find(|phase| phase.uid == uid && phase.end_action_value.is_none())
+    }
+
+    pub fn current_phase(&self, uid: u32) -> Option<&BossPhase> {
+        self.boss_phases
+            .iter()
+            .rev()
+            .find(|phase| phase.uid == uid && phase.end_action_value.is_none())
+    }
+
+    fn has_next_phase(&self, phase: &BossPhase) -> bool {
+        self.boss_phases
+            .iter()
+            .any(|later| later.boss_uid == phase.boss_uid && later.phase > phase.phase)
+    }
+
+    // Ends the entity's ongoing phase and starts the next one of the same boss
+    fn next_phase(&mut self, uid: u32, trigger: PhaseTrigger, max_hp: f64) {
+        let action_value = self.action_value;
+        let Some(previous) = self.current_phase_mut(uid) else {
+            return;
+        };
+        previous.end_action_value = Some(action_value);
+        let next = BossPhase {
+            phase: previous.phase + 1,
+            trigger,
+            max_hp,
+            damage: 0.,
+            start_action_value: action_value,
+            end_action_value: None,
+            ..previous.clone()
+        };
+        log::info!("{} entered phase {} ({:?})", next.name, next.phase, trigger);
+        self.boss_phases.push(next);
+    }
+
+    // Only elites and bosses are tracked, other enemies respawn with the same monster id
+    pub(super) fn track_boss_spawn(&mut self, enemy: &Enemy) {
+        if enemy.rank == EnemyRank::Normal {
+            return;
+        }
+
+        // Replacements spawn in the same wave at the AV their predecessor was defeated at,
+        // the latest defeated boss without a following phase is the one being replaced
+        let action_value = self.action_value;
+        let predecessor = self
+            .boss_phases
+            .iter()
+            .rev()
+            .find(|phase| {
+                let defeated_at_spawn = phase
+                    .end_action_value
+                    .is_some_and(|end| (end - action_value).abs() <= REPLACEMENT_AV_EPSILON);
+                phase.monster_id == enemy.id
+                    && phase.wave == enemy.wave
+                    && defeated_at_spawn
+                    && !self.has_next_phase(phase)
+            })
+            .cloned();
+
+        let phase = match predecessor {
+            Some(predecessor) => {
+                log::info!("{} was replaced by {}", predecessor.name, enemy.uid);
+                BossPhase {
+                    uid: enemy.uid,
+                    name: enemy.name.clone(),
+                    phase: predecessor.phase + 1,
+                    trigger: PhaseTrigger::Replacement,
+                    max_hp: enemy.base_stats.hp,
+                    damage: 0.,
+                    start_action_value: action_value,
+                    end_action_value: None,
+                    ..predecessor
+                }
+            }
+            None => BossPhase {
+                boss_uid: enemy.uid,
+                uid: enemy.uid,
+                monster_id: enemy.id,
+                name: enemy.name.clone(),
+                phase: 1,
+                trigger: PhaseTrigger::Spawn,
+                max_hp: enemy.base_stats.hp,
+                damage: 0.,
+                wave: enemy.wave,
+                start_action_value: action_value,
+                end_action_value: None,
+            },
+        };
+        self.boss_phases.push(phase);
+    }
+
+    // Takes the stat before it replaces the previously reported value
+    pub(super) fn track_boss_stat(&mut self, entity: &Entity, stat: &Stat) {
+        let Some(phase) = self.current_phase(entity.uid) else {
+            return;
+        };
+        let max_hp = phase.max_hp;
+        let previous = self
+            .stats_of(entity)
+            .and_then(|stats| stats.get(stat.kind()))
+            .copied()
+            .unwrap_or_default();
+
+        match *stat {
+            Stat::MaxHP(value) if (value - max_hp).abs() > MAX_HP_EPSILON => {
+                // The first report only confirms the HP the boss spawned with
+                if previous == 0. {
+                    if let Some(phase) = self.current_phase_mut(entity.uid) {
+                        phase.max_hp = value;
+                    }
+                } else {
+                    self.next_phase(entity.uid, PhaseTrigger::MaxHp, value);
+                }
+            }
+            // Rally and negative HP refill the bar once they kick in, so the refill is the
+            // next phase's HP. Changes while they're active are the pool being depleted
+            Stat::RallyHP(value) if value > 0. && previous == 0. => {
+                self.next_phase(entity.uid, PhaseTrigger::RallyHp, value);
+            }
+            Stat::NegativeHP(value) if value > 0. && previous == 0. => {
+                self.next_phase(entity.uid, PhaseTrigger::NegativeHp, value);
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn record_boss_damage(&mut self, defender: &Entity, damage: f64) {
+        if let Some(phase) = self.current_phase_mut(defender.uid) {
+            phase.damage += damage;
+        }
+    }
+
+    pub(super) fn end_boss_phase(&mut self, uid: u32) {
+        let action_value = self.action_value;
+        if let Some(phase) = self.current_phase_mut(uid) {
+            phase.end_action_value = Some(action_value);
+        }
+    }
+
+    pub fn boss_phase_summaries(&self) -> Vec<BossPhaseSummary> {
+        let mut summaries: Vec<BossPhaseSummary> = Vec::new();
+        for phase in &self.boss_phases {
+            match summaries
+                .iter_mut()
+                .find(|summary| summary.boss_uid == phase.boss_uid)
+            {
+                Some(summary) => {
+                    summary.phases = summary.phases.max(phase.phase);
+                    summary.total_hp += phase.max_hp;
+                    summary.total_damage += phase.damage;
+                }
+                None => summaries.push(BossPhaseSummary {
+                    boss_uid: phase.boss_uid,
+                    monster_id: phase.monster_id,
+                    name: phase.name.clone(),
+                    phases: phase.phase,
+                    total_hp: phase.max_hp,
+                    total_damage: phase.damage,
+                }),
+            }
+        }
+        summaries
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+use super::{BattleContext, BattleMode, scoring::ModeMetrics};
+use crate::models::misc::Avatar;
+
+// Both halves of a MoC or PF node share a stage id apart from the last digit, which is 1
+// for the first half and 2 for the second. AS stage ids aren't known to follow the same
+// layout, so their battles aren't grouped into runs
+pub fn node_of(stage_id: u32) -> u32 {
+    stage_id - stage_id % 10
+}
+
+pub fn half_of(stage_id: u32) -> Option<u32> {
+    match (stage_id, stage_id % 100) {
+        (30010000..=31000000, 21 | 22 | 41 | 42) => Some(stage_id % 10),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunHalf {
+    pub half: u32,
+    pub stage_id: u32,
+    pub avatars: Vec<Avatar>,
+    pub cycles_used: u32,
+    pub action_value: f64,
+    pub total_damage: f64,
+    pub turn_count: usize,
+    pub mode_metrics: Option<ModeMetrics>,
+}
+
+impl RunHalf {
+    pub fn totals(&self) -> RunTotals {
+        RunTotals {
+            cycles_used: self.cycles_used,
+            action_value: self.action_value,
+            total_damage: self.total_damage,
+            turn_count: self.turn_count,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunTotals {
+    pub cycles_used: u32,
+    pub action_value: f64,
+    pub total_damage: f64,
+    pub turn_count: usize,
+}
+
+impl RunTotals {
+    // Name/value pairs for flat outputs such as the CSV export
+    pub fn values(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("cycles_used", self.cycles_used as f64),
+            ("action_value", self.action_value),
+            ("total_damage", self.total_damage),
+            ("turn_count", self.turn_count as f64),
+        ]
+    }
+}
+
+// Consecutive battles fought on the two halves of the same endgame node
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndgameRun {
+    pub node_id: u32,
+    pub battle_mode: BattleMode,
+    // Sorted by half, a retried half replaces the previous attempt
+    pub halves: Vec<RunHalf>,
+}
+
+impl EndgameRun {
+    pub fn half(&self, half: u32) -> Option<&RunHalf> {
+        self.halves.iter().find(|run_half| run_half.half == half)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.half(1).is_some() && self.half(2).is_some()
+    }
+
+    pub fn totals(&self) -> RunTotals {
+        let mut totals = RunTotals::default();
+        for half in self.halves.iter().map(RunHalf::totals) {
+            totals.cycles_used += half.cycles_used;
+            totals.action_value += half.action_value;
+            totals.total_damage += half.total_damage;
+            totals.turn_count += half.turn_count;
+        }
+        totals
+    }
+}
+
+impl BattleContext {
+    // Called when a battle begins, keeps the current run only if this battle belongs to it
+    pub(super) fn enter_run(&mut self, stage_id: u32) {
+        let Some(half) = half_of(stage_id) else {
+            self.run = None;
+            return;
+        };
+        if self.battle_mode == BattleMode::Other {
+            self.run = None;
+            return;
+        }
+
+        let node_id = node_of(stage_id);
+        let continues_run = self
+            .run
+            .as_ref()
+            .is_some_and(|run| run.node_id == node_id && (half == 2 || run.half(2).is_none()));
+        if !continues_run {
+            self.run = Some(EndgameRun {
+                node_id,
+                battle_mode: self.battle_mode,
+                halves: Vec::new(),
+            });
+        }
+    }
+
+    // Called when a battle ends to record its result in the current run
+    pub(super) fn record_run_half(&mut self) {
+        let Some(half) = half_of(self.stage_id) else {
+            return;
+        };
+        let run_half = RunHalf {
+            half,
+            stage_id: self.stage_id,
+            avatars: self.avatar_lineup.clone(),
+            cycles_used: self.cycles_used(),
+            action_value: self.action_value,
+            total_damage: self.total_damage,
+            turn_count: self.turn_count,
+            mode_metrics: self.mode_metrics(),
+        };
+
+        let Some(run) = self.run.as_mut() else {
+            return;
+        };
+        run.halves.retain(|existing| existing.half != half);
+        run.halves.push(run_half);
+        run.halves.sort_by_key(|run_half| run_half.half);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // First and second half of a MoC node
+    const FIRST_HALF: u32 = 30012021;
+    const SECOND_HALF: u32 = 30012022;
+
+    fn fight(battle_context: &mut BattleContext, stage_id: u32, total_damage: f64) {
+        battle_context.battle_mode = BattleContext::get_battle_mode(stage_id);
+        battle_context.stage_id = stage_id;
+        battle_context.enter_run(stage_id);
+        battle_context.total_damage = total_damage;
+        battle_context.record_run_half();
+    }
+
+    #[test]
+    fn pairs_halves_of_moc_and_pf_nodes_only() {
+        assert_eq!(half_of(FIRST_HALF), Some(1));
+        assert_eq!(half_of(SECOND_HALF), Some(2));
+        assert_eq!(half_of(30012042), Some(2));
+        assert_eq!(node_of(FIRST_HALF), node_of(SECOND_HALF));
+        assert_eq!(half_of(420101), None);
+        assert_eq!(half_of(30012031), None);
+    }
+
+    #[test]
+    fn continues_run_with_second_half() {
+        let mut battle_context = BattleContext::default();
+        fight(&mut battle_context, FIRST_HALF, 100.);
+        fight(&mut battle_context, SECOND_HALF, 200.);
+
+        let run = battle_context.run.as_ref().unwrap();
+        assert!(run.is_complete());
+        assert_eq!(run.totals().total_damage, 300.);
+    }
+
+    #[test]
+    fn replaying_a_half_replaces_the_attempt() {
+        let mut battle_context = BattleContext::default();
+        fight(&mut battle_context, FIRST_HALF, 100.);
+        fight(&mut battle_context, FIRST_HALF, 150.);
+
+        let run = battle_context.run.as_ref().unwrap();
+        assert_eq!(run.halves.len(), 1);
+        assert_eq!(run.totals().total_damage, 150.);
+
+        // Once both halves are in, the first half starts a new run
+        fight(&mut battle_context, SECOND_HALF, 200.);
+        fight(&mut battle_context, FIRST_HALF, 120.);
+        let run = battle_context.run.as_ref().unwrap();
+        assert!(!run.is_complete());
+        assert_eq!(run.totals().total_damage, 120.);
+    }
+}
@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+
+use super::{BattleContext, BattleMode, cycle_at};
+use crate::models::misc::Team;
+
+// The game doesn't expose how PF and AS points are calculated, so only the inputs they're
+// scored on are tracked rather than a made up score
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "mode")]
+pub enum ModeMetrics {
+    MOC(MocMetrics),
+    PF(PfMetrics),
+    AS(AsMetrics),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MocMetrics {
+    pub cycles_used: u32,
+    pub cycle_limit: u32,
+    pub wave_action_values: Vec<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PfMetrics {
+    pub kills: u32,
+    pub overkill_damage: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AsMetrics {
+    pub boss_name: String,
+    pub boss_max_hp: f64,
+    pub boss_hp: f64,
+    // 0 to 1
+    pub progress: f64,
+    pub boss_defeated: bool,
+    // Cycles the limit still allows, counting the one in progress as used
+    pub cycles_left: u32,
+}
+
+impl ModeMetrics {
+    // Name/value pairs for flat outputs such as the CSV export
+    pub fn values(&self) -> Vec<(String, f64)> {
+        match self {
+            Self::MOC(metrics) => {
+                let mut values = vec![
+                    ("cycles_used".to_string(), metrics.cycles_used as f64),
+                    ("cycle_limit".to_string(), metrics.cycle_limit as f64),
+                ];
+                for (i, action_value) in metrics.wave_action_values.iter().enumerate() {
+                    values.push((format!("wave_{}_action_value", i + 1), *action_value));
+                }
+                values
+            }
+            Self::PF(metrics) => vec![
+                ("kills".to_string(), metrics.kills as f64),
+                ("overkill_damage".to_string(), metrics.overkill_damage),
+            ],
+            Self::AS(metrics) => vec![
+                ("boss_max_hp".to_string(), metrics.boss_max_hp),
+                ("boss_hp".to_string(), metrics.boss_hp),
+                ("progress".to_string(), metrics.progress),
+                (
+                    "boss_defeated".to_string(),
+                    metrics.boss_defeated as u32 as f64,
+                ),
+                ("cycles_left".to_string(), metrics.cycles_left as f64),
+            ],
+        }
+    }
+}
+
+impl BattleContext {
+    pub fn mode_metrics(&self) -> Option<ModeMetrics> {
+        match self.battle_mode {
+            BattleMode::MOC => Some(ModeMetrics::MOC(self.moc_metrics())),
+            BattleMode::PF => Some(ModeMetrics::PF(self.pf_metrics())),
+            BattleMode::AS => self.as_metrics().map(ModeMetrics::AS),
+            BattleMode::Other => None,
+        }
+    }
+
+    // The game only reports cycle changes, so a battle still in its first cycle or a mode
+    // without cycle updates falls back to the cycle derived from AV
+    pub fn cycles_used(&self) -> u32 {
+        if self.max_cycle > 0 {
+            self.max_cycle + 1
+        } else {
+            cycle_at(self.action_value) + 1
+        }
+    }
+
+    // AV spent in each wave
+    pub fn wave_action_values(&self) -> Vec<f64> {
+        self.wave_segments
+            .iter()
+            .map(|segment| segment.action_value(self.action_value))
+            .collect()
+    }
+
+    fn moc_metrics(&self) -> MocMetrics {
+        MocMetrics {
+            cycles_used: self.cycles_used(),
+            cycle_limit: self.cycle_limit,
+            wave_action_values: self.wave_action_values(),
+        }
+    }
+
+    fn pf_metrics(&self) -> PfMetrics {
+        let kills = self
+            .defeat_history
+            .iter()
+            .filter(|defeat| defeat.entity_defeated.team == Team::Enemy)
+            .count() as u32;
+
+        PfMetrics {
+            kills,
+            overkill_damage: self.real_time_overkill_damages.iter().sum(),
+        }
+    }
+
+    // The boss is the tracked elite or boss with the most HP across its phases, and its
+    // HP bar is every phase's HP added up, so refills and replacements count towards it
+    fn as_metrics(&self) -> Option<AsMetrics> {
+        let boss = self
+            .boss_phase_summaries()
+            .into_iter()
+            .max_by(|a, b| a.total_hp.total_cmp(&b.total_hp))?;
+        let phases = self
+            .boss_phases
+            .iter()
+            .filter(|phase| phase.boss_uid == boss.boss_uid)
+            .collect::<Vec<_>>();
+        // Damage past a phase's HP is overkill that doesn't carry over
+        let damage = phases
+            .iter()
+            .map(|phase| phase.damage.min(phase.max_hp))
+            .sum::<f64>();
+        let boss_max_hp = boss.total_hp;
+        let boss_hp = (boss_max_hp - damage).max(0.);
+        let progress = if boss_max_hp > 0. {
+            (damage / boss_max_hp).clamp(0., 1.)
+        } else {
+            0.
+        };
+        // Phases only end without a following one when their entity is defeated
+        let boss_defeated = phases
+            .last()
+            .is_some_and(|phase| phase.end_action_value.is_some());
+        Some(AsMetrics {
+            boss_name: boss.name,
+            boss_max_hp,
+            boss_hp,
+            progress,
+            boss_defeated,
+            cycles_left: self.cycle_limit.saturating_sub(self.cycles_used()),
+        })
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use super::BattleContext;
+use crate::models::misc::Avatar;
+
+// Damage a servant dealt on behalf of its owner, also counted in the owner's damage
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummonDamage {
+    pub id: u32,
+    pub name: String,
+    pub owner_id: u32,
+    pub damage: f64,
+}
+
+impl BattleContext {
+    pub(super) fn record_summon_damage(&mut self, owner_id: u32, summon: &Avatar, damage: f64) {
+        match self
+            .summons
+            .iter_mut()
+            .find(|entry| entry.owner_id == owner_id && entry.id == summon.id)
+        {
+            Some(entry) => entry.damage += damage,
+            None => self.summons.push(SummonDamage {
+                id: summon.id,
+                name: summon.name.clone(),
+                owner_id,
+                damage,
+            }),
+        }
+    }
+
+    pub fn summons_of(&self, owner_id: u32) -> impl Iterator<Item = &SummonDamage> {
+        self.summons
+            .iter()
+            .filter(move |summon| summon.owner_id == owner_id)
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use super::BattleContext;
+
+// RPG.GameCore.AttackType values of damage dealt by techniques in the overworld
+const MAZE_ATTACK_TYPES: [isize; 2] = [7, 8];
+
+pub fn is_technique_damage(damage_type: isize) -> bool {
+    MAZE_ATTACK_TYPES.contains(&damage_type)
+}
+
+// Damage dealt before the first turn, kept apart from the turn it would otherwise be merged into
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TechniquePhase {
+    // Index w/ lineup index
+    pub avatars_damage: Vec<f64>,
+    pub total_damage: f64,
+    pub hits: u32,
+    // Hits typed as Maze or MazeNormal, the rest are e.g. DoTs or follow-ups the opener triggered
+    pub technique_hits: u32,
+}
+
+impl BattleContext {
+    // Nothing has taken a turn yet
+    pub fn in_technique_phase(&self) -> bool {
+        self.current_turn_battle_id == 0
+    }
+
+    pub(super) fn record_technique_damage(
+        &mut self,
+        lineup_index: usize,
+        damage: f64,
+        damage_type: isize,
+    ) {
+        let phase = &mut self.technique_phase;
+        phase.avatars_damage[lineup_index] += damage;
+        phase.total_damage += damage;
+        phase.hits += 1;
+        if is_technique_damage(damage_type) {
+            phase.technique_hits += 1;
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use super::{BASE_ACTION_GAUGE, BattleContext, SkillCategory};
+use crate::models::misc::{Entity, Team};
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum ActionKind {
+    Turn,
+    Ultimate,
+    FollowUp,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineAction {
+    pub entity: Entity,
+    pub action_value: f64,
+    pub wave: u32,
+    pub cycle: u32,
+    pub kind: ActionKind,
+}
+
+#[derive(Clone, Debug)]
+pub struct PredictedAction {
+    pub entity: Entity,
+    pub action_value: f64,
+}
+
+impl BattleContext {
+    // Every turn taken plus the ultimates and follow-ups used in between, ordered by AV
+    pub fn action_timeline(&self) -> Vec<TimelineAction> {
+        let mut timeline = self
+            .entity_turn_history
+            .iter()
+            .map(|(entity, action_value, wave, cycle)| TimelineAction {
+                entity: entity.clone(),
+                action_value: *action_value,
+                wave: *wave,
+                cycle: *cycle,
+                kind: ActionKind::Turn,
+            })
+            .collect::<Vec<_>>();
+
+        for skill in &self.skill_history {
+            let kind = match skill.category() {
+                SkillCategory::Ultimate => ActionKind::Ultimate,
+                SkillCategory::Talent => ActionKind::FollowUp,
+                _ => continue,
+            };
+            let (action_value, wave, cycle) = self.skill_turn(skill).map_or(
+                (0., self.wave, self.cycle),
+                |(_, action_value, wave, cycle)| (*action_value, *wave, *cycle),
+            );
+            timeline.push(TimelineAction {
+                entity: Entity {
+                    uid: skill.avatar_id,
+                    team: Team::Player,
+                },
+                action_value,
+                wave,
+                cycle,
+                kind,
+            });
+        }
+
+        timeline.sort_by(|a, b| a.action_value.total_cmp(&b.action_value));
+        timeline
+    }
+
+    fn is_defeated_enemy(&self, uid: u32) -> bool {
+        self.defeat_history.iter().any(|defeat| {
+            defeat.entity_defeated.team == Team::Enemy && defeat.entity_defeated.uid == uid
+        })
+    }
+
+    // Upcoming turns from each living entity's remaining AV and speed
+    pub fn predicted_actions(&self, count: usize) -> Vec<PredictedAction> {
+        let avatars = self
+            .battle_avatars
+            .iter()
+            .filter(|avatar| !self.is_down(avatar.entity.uid));
+        let enemies = self.battle_enemies.iter().filter(|enemy| {
+            enemy.battle_stats.hp > 0. && !self.is_defeated_enemy(enemy.entity.uid)
+        });
+
+        let mut predicted = Vec::new();
+        for battle_entity in avatars.chain(enemies) {
+            let stats = &battle_entity.battle_stats;
+            if stats.speed <= 0. {
+                continue;
+            }
+            let interval = BASE_ACTION_GAUGE / stats.speed;
+            let mut action_value = self.action_value + stats.av.max(0.);
+            for _ in 0..count {
+                predicted.push(PredictedAction {
+                    entity: battle_entity.entity.clone(),
+                    action_value,
+                });
+                action_value += interval;
+            }
+        }
+
+        predicted.sort_by(|a, b| a.action_value.total_cmp(&b.action_value));
+        predicted.truncate(count);
+        predicted
+    }
+
+    pub fn entity_name(&self, entity: &Entity) -> String {
+        match entity.team {
+            Team::Player => self
+                .avatar_lineup
+                .iter()
+                .find(|avatar| avatar.id == entity.uid)
+                .map(|avatar| avatar.name.clone()),
+            Team::Enemy => self
+                .enemies
+                .iter()
+                .find(|enemy| enemy.uid == entity.uid)
+                .map(|enemy| enemy.name.clone()),
+        }
+        .unwrap_or_else(|| entity.uid.to_string())
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+use super::BattleContext;
+use crate::models::misc::Entity;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveSegment {
+    pub wave: u32,
+    pub start_action_value: f64,
+    // None until the wave is over
+    pub end_action_value: Option<f64>,
+    // Index w/ lineup index
+    pub avatars_damage: Vec<f64>,
+    pub total_damage: f64,
+    pub turn_count: usize,
+    pub kills: u32,
+    pub first_action: Option<Entity>,
+}
+
+impl WaveSegment {
+    // AV spent in the wave so far
+    pub fn action_value(&self, current_action_value: f64) -> f64 {
+        (self.end_action_value.unwrap_or(current_action_value) - self.start_action_value).max(0.)
+    }
+}
+
+impl BattleContext {
+    // Segment of the current wave, closing the previous one if the wave changed since
+    pub(super) fn current_wave_segment_mut(&mut self) -> &mut WaveSegment {
+        let action_value = self.action_value;
+        let starts_new_segment = self
+            .wave_segments
+            .last()
+            .is_none_or(|segment| segment.wave != self.wave);
+        if starts_new_segment {
+            if let Some(previous) = self.wave_segments.last_mut() {
+                previous.end_action_value.get_or_insert(action_value);
+            }
+            self.wave_segments.push(WaveSegment {
+                wave: self.wave,
+                start_action_value: action_value,
+                end_action_value: None,
+                avatars_damage: vec![0f64; self.avatar_lineup.len()],
+                total_damage: 0.,
+                turn_count: 0,
+                kills: 0,
+                first_action: None,
+            });
+        }
+        self.wave_segments.last_mut().unwrap()
+    }
+
+    pub(super) fn close_wave_segment(&mut self) {
+        let action_value = self.action_value;
+        if let Some(segment) = self.wave_segments.last_mut() {
+            segment.end_action_value.get_or_insert(action_value);
+        }
+    }
+}
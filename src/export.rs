@@ -5,9 +5,31 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use directories::BaseDirs;
 use chrono::DateTime;
 
-use crate::battle::BattleContext;
+use crate::analysis::{
+    attribution::{BuffRecord, DamageAttribution},
+    damage_formula::{HitCheck, SKILL_MULTIPLIERS},
+    rotation::AvatarRotationReport,
+    speed::{AvatarSpeedReport, GaugeShiftKind},
+};
+use crate::battle::{
+    BattleContext, DefeatRecord, TurnOwnerDamage,
+    actions::OutOfTurnAction,
+    cycles::CycleSegment,
+    downtime::AvatarDeath,
+    enemy_hp::{EnemyHpSample, EnemyTimeToKill},
+    phases::{BossPhase, BossPhaseSummary},
+    run::{EndgameRun, RunTotals},
+    scoring::ModeMetrics,
+    summons::SummonDamage,
+    technique::TechniquePhase,
+    timeline::TimelineAction,
+    waves::WaveSegment,
+};
+use crate::models::misc::{
+    AvatarBuild, BattleStats, Element, ElementResistance, EnemyRank, Entity, LineupChange, Team,
+};
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct ComprehensiveData {
     pub data_type: String,
     pub character_name: String,
@@ -33,6 +55,11 @@ pub struct ComprehensiveData {
     pub cumulative_damage: Option<f64>,
     pub cumulative_character_damage: Option<f64>,
     pub skill_damage_percentage: Option<f64>,
+    pub metric: Option<String>,
+    pub metric_value: Option<f64>,
+    pub half: Option<u32>,
+    pub kills: Option<u32>,
+    pub first_action_uid: Option<u32>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -41,6 +68,8 @@ pub struct ExportAvatarBattleInfo {
     pub avatar_id: u32,
     #[serde(rename = "isDie")]
     pub is_die: bool,
+    #[serde(default)]
+    pub build: Option<AvatarBuild>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -53,6 +82,8 @@ pub struct ExportTurnBattleInfo {
     pub wave_index: u32,
     #[serde(rename = "cycleIndex")]
     pub cycle_index: u32,
+    #[serde(default)]
+    pub team: Option<Team>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -76,6 +107,8 @@ pub struct ExportSkillBattleInfo {
     pub skill_name: String,
     #[serde(rename = "turnBattleId")]
     pub turn_battle_id: u32,
+    #[serde(rename = "outOfTurn", default)]
+    pub out_of_turn: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -85,6 +118,11 @@ pub struct ExportAvatarDetail {
     pub is_die: bool,
     #[serde(rename = "killer_uid")]
     pub killer_uid: i32,
+    #[serde(default)]
+    pub deaths: u32,
+    // AV spent down across every death
+    #[serde(default)]
+    pub downtime: f64,
     pub stats: HashMap<String, f64>,
     #[serde(rename = "statsHistory")]
     pub stats_history: Vec<ExportStatsHistory>,
@@ -95,6 +133,9 @@ pub struct ExportStatsHistory {
     pub stats: HashMap<String, f64>,
     #[serde(rename = "turnBattleId")]
     pub turn_battle_id: u32,
+    // AV of the last change in the turn
+    #[serde(rename = "actionValue", default)]
+    pub action_value: f64,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -135,6 +176,16 @@ pub struct ExportEnemyDetail {
     pub stats: HashMap<String, f64>,
     #[serde(rename = "statsHistory")]
     pub stats_history: Vec<ExportStatsHistory>,
+    #[serde(default)]
+    pub weaknesses: Vec<Element>,
+    #[serde(default)]
+    pub resistances: Vec<ElementResistance>,
+    #[serde(rename = "maxToughness", default)]
+    pub max_toughness: f64,
+    #[serde(default)]
+    pub rank: EnemyRank,
+    #[serde(rename = "eliteGroup", default)]
+    pub elite_group: u32,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -165,6 +216,48 @@ pub struct ExportBattleData {
     pub avatar_detail: HashMap<String, ExportAvatarDetail>,
     #[serde(rename = "enemyDetail")]
     pub enemy_detail: HashMap<String, ExportEnemyDetail>,
+    #[serde(rename = "defeatHistory")]
+    pub defeat_history: Vec<DefeatRecord>,
+    #[serde(rename = "modeMetrics")]
+    pub mode_metrics: Option<ModeMetrics>,
+    #[serde(rename = "waveSegments")]
+    pub wave_segments: Vec<WaveSegment>,
+    #[serde(rename = "cycleSegments")]
+    pub cycle_segments: Vec<CycleSegment>,
+    #[serde(rename = "actionTimeline")]
+    pub action_timeline: Vec<TimelineAction>,
+    #[serde(rename = "speedAnalysis")]
+    pub speed_analysis: Vec<AvatarSpeedReport>,
+    #[serde(rename = "rotationAnalysis")]
+    pub rotation_analysis: Vec<AvatarRotationReport>,
+    #[serde(rename = "damageAttribution")]
+    pub damage_attribution: Vec<DamageAttribution>,
+    #[serde(rename = "buffHistory")]
+    pub buff_history: Vec<BuffRecord>,
+    #[serde(rename = "hitChecks")]
+    pub hit_checks: Vec<HitCheck>,
+    pub summons: Vec<SummonDamage>,
+    #[serde(rename = "outOfTurnActions")]
+    pub out_of_turn_actions: Vec<OutOfTurnAction>,
+    #[serde(rename = "turnOwnerDamage")]
+    pub turn_owner_damage: Vec<TurnOwnerDamage>,
+    #[serde(rename = "lineupChanges")]
+    pub lineup_changes: Vec<LineupChange>,
+    #[serde(rename = "avatarDeaths")]
+    pub avatar_deaths: Vec<AvatarDeath>,
+    #[serde(rename = "enemyHpHistory")]
+    pub enemy_hp_history: Vec<EnemyHpSample>,
+    #[serde(rename = "enemyTimeToKill")]
+    pub enemy_time_to_kill: Vec<EnemyTimeToKill>,
+    #[serde(rename = "bossPhases")]
+    pub boss_phases: Vec<BossPhase>,
+    #[serde(rename = "bossPhaseSummaries")]
+    pub boss_phase_summaries: Vec<BossPhaseSummary>,
+    #[serde(rename = "techniquePhase")]
+    pub technique_phase: TechniquePhase,
+    pub run: Option<EndgameRun>,
+    #[serde(rename = "runTotals")]
+    pub run_totals: Option<RunTotals>,
 }
 
 pub struct BattleDataExporter;
@@ -191,25 +284,69 @@ impl BattleDataExporter {
             .as_secs()
     }
 
-    fn build_stats_map(battle_stats: &crate::models::misc::BattleStats) -> HashMap<String, f64> {
+    fn build_stats_map(
+        battle_context: &BattleContext,
+        entity: &Entity,
+        battle_stats: Option<&BattleStats>,
+    ) -> HashMap<String, f64> {
         let mut stats = HashMap::new();
-        stats.insert("HP".to_string(), battle_stats.hp);
-        stats.insert("Attack".to_string(), battle_stats.attack);
-        stats.insert("Defense".to_string(), battle_stats.defense);
-        stats.insert("Speed".to_string(), battle_stats.speed);
-        stats.insert("AV".to_string(), battle_stats.av);
+        if let Some(battle_stats) = battle_stats {
+            stats.insert("HP".to_string(), battle_stats.hp);
+            stats.insert("Attack".to_string(), battle_stats.attack);
+            stats.insert("Defense".to_string(), battle_stats.defense);
+            stats.insert("Speed".to_string(), battle_stats.speed);
+            stats.insert("AV".to_string(), battle_stats.av);
+        }
+        if let Some(reported) = battle_context.stats_of(entity) {
+            stats.extend(
+                reported
+                    .iter()
+                    .map(|(stat, value)| (stat.to_string(), *value)),
+            );
+        }
         stats
     }
 
-    fn create_stats_history(stats: &HashMap<String, f64>) -> Vec<ExportStatsHistory> {
-        if stats.is_empty() {
-            Vec::new()
-        } else {
-            vec![ExportStatsHistory {
+    // One snapshot of every stat seen so far per turn in which the entity's stats changed
+    fn create_stats_history(
+        battle_context: &BattleContext,
+        entity: &Entity,
+        stats: &HashMap<String, f64>,
+    ) -> Vec<ExportStatsHistory> {
+        let mut history: Vec<ExportStatsHistory> = Vec::new();
+        for change in battle_context
+            .stat_history
+            .iter()
+            .filter(|change| change.entity == *entity && change.entity.team == entity.team)
+        {
+            if history
+                .last()
+                .is_none_or(|snapshot| snapshot.turn_battle_id != change.turn_battle_id)
+            {
+                let stats = history
+                    .last()
+                    .map(|snapshot| snapshot.stats.clone())
+                    .unwrap_or_default();
+                history.push(ExportStatsHistory {
+                    stats,
+                    turn_battle_id: change.turn_battle_id,
+                    action_value: change.action_value,
+                });
+            }
+            if let Some(snapshot) = history.last_mut() {
+                snapshot.stats.insert(change.stat.to_string(), change.value);
+                snapshot.action_value = change.action_value;
+            }
+        }
+
+        if history.is_empty() && !stats.is_empty() {
+            history.push(ExportStatsHistory {
                 stats: stats.clone(),
                 turn_battle_id: Self::INITIAL_TURN_BATTLE_ID,
-            }]
+                action_value: 0.,
+            });
         }
+        history
     }
 
     fn calculate_damage_per_av(total_damage: f64, action_value: f64) -> f64 {
@@ -269,7 +406,8 @@ impl BattleDataExporter {
             .iter()
             .map(|avatar| ExportAvatarBattleInfo {
                 avatar_id: avatar.id,
-                is_die: false,
+                is_die: battle_context.is_down(avatar.id),
+                build: avatar.build.clone(),
             })
             .collect();
         let mut turn_history = Vec::new();
@@ -279,6 +417,7 @@ impl BattleDataExporter {
             action_value: 0.0,
             wave_index: battle_context.wave,
             cycle_index: battle_context.max_cycle,
+            team: None,
         });
         for (entity, action_value, wave, cycle) in &battle_context.entity_turn_history {
             turn_history.push(ExportTurnBattleInfo {
@@ -286,6 +425,7 @@ impl BattleDataExporter {
                 action_value: *action_value,
                 wave_index: *wave,
                 cycle_index: *cycle,
+                team: Some(entity.team.clone()),
             });
         }
 
@@ -306,26 +446,39 @@ impl BattleDataExporter {
                 skill_type: skill.skill_type,
                 skill_name: skill.skill_name.clone(),
                 turn_battle_id: skill.turn_battle_id,
+                out_of_turn: skill.out_of_turn,
             })
             .collect();
 
         let mut avatar_detail = HashMap::new();
         for avatar in &battle_context.avatar_lineup {
-            let stats = battle_context
+            let entity = Entity {
+                uid: avatar.id,
+                team: Team::Player,
+            };
+            let battle_stats = battle_context
                 .battle_avatars
                 .iter()
-                .find(|ba| ba.entity.uid == avatar.id)
-                .map(|ba| Self::build_stats_map(&ba.battle_stats))
-                .unwrap_or_default();
+                .find(|ba| ba.entity == entity)
+                .map(|ba| &ba.battle_stats);
+            let stats = Self::build_stats_map(battle_context, &entity, battle_stats);
             
-            let stats_history = Self::create_stats_history(&stats);
+            let stats_history = Self::create_stats_history(battle_context, &entity, &stats);
+
+            // Revived avatars aren't dead, their deaths still count towards `deaths`
+            let is_die = battle_context.is_down(avatar.id);
+            let last_death = battle_context.deaths_of(avatar.id).last();
 
             avatar_detail.insert(
                 avatar.id.to_string(),
                 ExportAvatarDetail {
                     id: avatar.id,
-                    is_die: false,
-                    killer_uid: Self::DEFAULT_KILLER_ID,
+                    is_die,
+                    killer_uid: last_death
+                        .filter(|_| is_die)
+                        .map_or(Self::DEFAULT_KILLER_ID, |death| death.killer.uid as i32),
+                    deaths: battle_context.deaths_of(avatar.id).count() as u32,
+                    downtime: battle_context.total_downtime(avatar.id),
                     stats,
                     stats_history,
                 },
@@ -333,29 +486,44 @@ impl BattleDataExporter {
         }
 
         let mut enemy_detail = HashMap::new();
-        for (index, enemy) in battle_context.enemies.iter().enumerate() {
-            let stats = battle_context
+        for enemy in &battle_context.enemies {
+            let entity = Entity {
+                uid: enemy.uid,
+                team: Team::Enemy,
+            };
+            let battle_stats = battle_context
                 .battle_enemies
                 .iter()
-                .find(|be| be.entity.uid == enemy.uid)
-                .map(|be| Self::build_stats_map(&be.battle_stats))
-                .unwrap_or_default();
+                .find(|be| be.entity == entity)
+                .map(|be| &be.battle_stats);
+            let stats = Self::build_stats_map(battle_context, &entity, battle_stats);
             
-            let stats_history = Self::create_stats_history(&stats);
+            let stats_history = Self::create_stats_history(battle_context, &entity, &stats);
+
+            let defeat = battle_context.defeat_history.iter().find(|defeat| {
+                defeat.entity_defeated.team == Team::Enemy
+                    && defeat.entity_defeated.uid == enemy.uid
+            });
 
             enemy_detail.insert(
                 enemy.uid.to_string(),
                 ExportEnemyDetail {
                     id: enemy.id,
-                    is_die: false,
-                    killer_uid: Self::DEFAULT_KILLER_ID,
-                    position_index: index as u32,
-                    wave_index: battle_context.wave,
+                    is_die: defeat.is_some(),
+                    killer_uid: defeat
+                        .map_or(Self::DEFAULT_KILLER_ID, |defeat| defeat.killer.uid as i32),
+                    position_index: enemy.position,
+                    wave_index: enemy.wave,
                     name: enemy.name.clone(),
                     max_hp: enemy.base_stats.hp,
                     level: enemy.base_stats.level,
                     stats,
                     stats_history,
+                    weaknesses: enemy.weaknesses.clone(),
+                    resistances: enemy.resistances.clone(),
+                    max_toughness: enemy.max_toughness,
+                    rank: enemy.rank,
+                    elite_group: enemy.elite_group,
                 },
             );
         }
@@ -375,6 +543,28 @@ impl BattleDataExporter {
             version: env!("CARGO_PKG_VERSION").to_string(),
             avatar_detail,
             enemy_detail,
+            defeat_history: battle_context.defeat_history.clone(),
+            mode_metrics: battle_context.mode_metrics(),
+            wave_segments: battle_context.wave_segments.clone(),
+            cycle_segments: battle_context.cycle_segments.clone(),
+            action_timeline: battle_context.action_timeline(),
+            speed_analysis: battle_context.speed_report(),
+            rotation_analysis: battle_context.rotation_report(),
+            damage_attribution: battle_context.damage_attribution(),
+            buff_history: battle_context.buff_history.clone(),
+            hit_checks: battle_context.hit_checks(&SKILL_MULTIPLIERS),
+            summons: battle_context.summons.clone(),
+            out_of_turn_actions: battle_context.out_of_turn_actions.clone(),
+            turn_owner_damage: battle_context.turn_owner_damage(),
+            lineup_changes: battle_context.lineup_changes.clone(),
+            avatar_deaths: battle_context.avatar_deaths.clone(),
+            enemy_hp_history: battle_context.enemy_hp_history.clone(),
+            enemy_time_to_kill: battle_context.enemy_time_to_kill(),
+            boss_phases: battle_context.boss_phases.clone(),
+            boss_phase_summaries: battle_context.boss_phase_summaries(),
+            technique_phase: battle_context.technique_phase.clone(),
+            run: battle_context.run.clone(),
+            run_totals: battle_context.run.as_ref().map(EndgameRun::totals),
         }
     }
 
@@ -505,6 +695,7 @@ impl BattleDataExporter {
                 cumulative_damage: None,
                 cumulative_character_damage: None,
                 skill_damage_percentage: None,
+                ..Default::default()
             });
         }
 
@@ -559,9 +750,375 @@ impl BattleDataExporter {
                 } else {
                     0.0
                 }),
+                ..Default::default()
+            });
+        }
+
+        for segment in &battle_context.wave_segments {
+            let wave_action_value = segment.action_value(total_action_value);
+            all_data.push(ComprehensiveData {
+                data_type: "wave_summary".to_string(),
+                wave: Some(segment.wave),
+                action_value: Some(wave_action_value),
+                total_damage: Some(segment.total_damage),
+                dpav: Some(Self::calculate_damage_per_av(segment.total_damage, wave_action_value)),
+                turns_taken: Some(segment.turn_count as u32),
+                kills: Some(segment.kills),
+                first_action_uid: segment.first_action.as_ref().map(|entity| entity.uid),
+                ..Default::default()
+            });
+
+            for (i, avatar) in battle_context.avatar_lineup.iter().enumerate() {
+                let character_damage = segment.avatars_damage.get(i).copied().unwrap_or(0.0);
+                all_data.push(ComprehensiveData {
+                    data_type: "wave_character".to_string(),
+                    character_name: avatar.name.clone(),
+                    character_id: avatar.id,
+                    wave: Some(segment.wave),
+                    total_damage: Some(character_damage),
+                    damage_percentage: Some(if segment.total_damage > 0.0 {
+                        (character_damage / segment.total_damage) * 100.0
+                    } else {
+                        0.0
+                    }),
+                    dpav: Some(Self::calculate_damage_per_av(character_damage, wave_action_value)),
+                    ..Default::default()
+                });
+            }
+        }
+
+        for segment in &battle_context.cycle_segments {
+            all_data.push(ComprehensiveData {
+                data_type: "cycle_summary".to_string(),
+                cycle: Some(segment.cycle),
+                action_value: Some(segment.start_action_value),
+                total_damage: Some(segment.total_damage),
+                turns_taken: Some(segment.avatars_actions.iter().sum()),
+                ..Default::default()
+            });
+
+            for (i, avatar) in battle_context.avatar_lineup.iter().enumerate() {
+                all_data.push(ComprehensiveData {
+                    data_type: "cycle_character".to_string(),
+                    character_name: avatar.name.clone(),
+                    character_id: avatar.id,
+                    cycle: Some(segment.cycle),
+                    action_value: Some(segment.start_action_value),
+                    total_damage: Some(segment.avatars_damage.get(i).copied().unwrap_or(0.0)),
+                    turns_taken: Some(segment.avatars_actions.get(i).copied().unwrap_or(0)),
+                    ..Default::default()
+                });
+            }
+        }
+
+        for report in battle_context.speed_report() {
+            let mut values = vec![
+                ("speed", report.speed),
+                ("actions", report.actions as f64),
+                ("advances", report.count_shifts(GaugeShiftKind::Advance) as f64),
+                ("delays", report.count_shifts(GaugeShiftKind::Delay) as f64),
+            ];
+            if let Some(interval) = report.average_action_interval {
+                values.push(("average_action_interval", interval));
+            }
+            if let Some(interval) = report.expected_action_interval {
+                values.push(("expected_action_interval", interval));
+            }
+            for (metric, value) in values {
+                all_data.push(ComprehensiveData {
+                    data_type: "speed_summary".to_string(),
+                    character_name: report.name.clone(),
+                    character_id: report.avatar_id,
+                    metric: Some(metric.to_string()),
+                    metric_value: Some(value),
+                    ..Default::default()
+                });
+            }
+        }
+
+        for report in battle_context.rotation_report() {
+            let character_row = || ComprehensiveData {
+                character_name: report.name.clone(),
+                character_id: report.avatar_id,
+                ..Default::default()
+            };
+
+            if let Some(rotation) = &report.rotation {
+                all_data.push(ComprehensiveData {
+                    data_type: "rotation".to_string(),
+                    skill_name: Some(rotation.clone()),
+                    ..character_row()
+                });
+            }
+            for sequence in &report.top_sequences {
+                all_data.push(ComprehensiveData {
+                    data_type: "rotation_sequence".to_string(),
+                    skill_name: Some(sequence.sequence.clone()),
+                    action_value: Some(sequence.action_value),
+                    skill_damage: Some(sequence.damage),
+                    ..character_row()
+                });
+            }
+            for (cycle, counts) in report.uses_per_cycle.iter().enumerate() {
+                for (metric, value) in counts.values() {
+                    all_data.push(ComprehensiveData {
+                        data_type: "skill_uses".to_string(),
+                        cycle: Some(cycle as u32),
+                        metric: Some(metric.to_string()),
+                        metric_value: Some(value as f64),
+                        ..character_row()
+                    });
+                }
+            }
+            for action_value in &report.wasted_ultimates {
+                all_data.push(ComprehensiveData {
+                    data_type: "wasted_ultimate".to_string(),
+                    action_value: Some(*action_value),
+                    ..character_row()
+                });
+            }
+        }
+
+        for attribution in battle_context.damage_attribution() {
+            for (metric, value) in [
+                ("enabled_damage", attribution.enabled_damage),
+                ("enabled_by_others", attribution.enabled_by_others),
+                ("attributed_damage", attribution.attributed_damage),
+            ] {
+                all_data.push(ComprehensiveData {
+                    data_type: "damage_attribution".to_string(),
+                    character_name: attribution.name.clone(),
+                    character_id: attribution.avatar_id,
+                    total_damage: Some(attribution.damage),
+                    metric: Some(metric.to_string()),
+                    metric_value: Some(value),
+                    ..Default::default()
+                });
+            }
+        }
+
+        for split in battle_context.turn_owner_damage() {
+            for (metric, value) in [
+                ("ally_turn_damage", split.ally_turn_damage),
+                ("enemy_turn_damage", split.enemy_turn_damage),
+            ] {
+                all_data.push(ComprehensiveData {
+                    data_type: "turn_owner_damage".to_string(),
+                    character_name: split.name.clone(),
+                    character_id: split.avatar_id,
+                    metric: Some(metric.to_string()),
+                    metric_value: Some(value),
+                    ..Default::default()
+                });
+            }
+        }
+
+        for action in &battle_context.out_of_turn_actions {
+            all_data.push(ComprehensiveData {
+                data_type: "out_of_turn_action".to_string(),
+                character_name: battle_context.entity_name(&action.entity),
+                character_id: action.entity.uid,
+                turn_battle_id: Some(action.parent_turn_battle_id),
+                wave: Some(action.wave),
+                cycle: Some(action.cycle),
+                action_value: Some(action.action_value),
+                skill_name: Some(action.skill_name.clone()),
+                skill_type: Some(action.skill_type),
+                skill_damage: Some(action.total_damage),
+                metric: action.trigger.as_ref().map(|_| "trigger_uid".to_string()),
+                metric_value: action.trigger.as_ref().map(|trigger| trigger.uid as f64),
+                ..Default::default()
+            });
+        }
+
+        for (i, avatar) in battle_context.avatar_lineup.iter().enumerate() {
+            let damage = battle_context
+                .technique_phase
+                .avatars_damage
+                .get(i)
+                .copied()
+                .unwrap_or_default();
+            if damage > 0.0 {
+                all_data.push(ComprehensiveData {
+                    data_type: "technique_phase".to_string(),
+                    character_name: avatar.name.clone(),
+                    character_id: avatar.id,
+                    total_damage: Some(damage),
+                    damage_percentage: (total_damage > 0.0).then(|| damage / total_damage * 100.0),
+                    ..Default::default()
+                });
+            }
+        }
+
+        for phase in &battle_context.boss_phases {
+            all_data.push(ComprehensiveData {
+                data_type: "boss_phase".to_string(),
+                character_name: phase.name.clone(),
+                character_id: phase.uid,
+                total_damage: Some(phase.damage),
+                damage_percentage: (total_damage > 0.0)
+                    .then(|| phase.damage / total_damage * 100.0),
+                wave: Some(phase.wave),
+                action_value: Some(phase.start_action_value),
+                metric: Some(format!("phase_{}_max_hp", phase.phase)),
+                metric_value: Some(phase.max_hp),
+                ..Default::default()
+            });
+        }
+
+        for summary in battle_context.boss_phase_summaries() {
+            all_data.push(ComprehensiveData {
+                data_type: "boss_total".to_string(),
+                character_name: summary.name.clone(),
+                character_id: summary.boss_uid,
+                total_damage: Some(summary.total_damage),
+                damage_percentage: (total_damage > 0.0)
+                    .then(|| summary.total_damage / total_damage * 100.0),
+                metric: Some("total_hp".to_string()),
+                metric_value: Some(summary.total_hp),
+                ..Default::default()
+            });
+        }
+
+        for ttk in battle_context.enemy_time_to_kill() {
+            all_data.push(ComprehensiveData {
+                data_type: "enemy_time_to_kill".to_string(),
+                character_name: ttk.name.clone(),
+                character_id: ttk.uid,
+                total_damage: Some(ttk.damage_taken),
+                damage_percentage: (total_damage > 0.0)
+                    .then(|| ttk.damage_taken / total_damage * 100.0),
+                wave: Some(ttk.wave),
+                action_value: Some(ttk.spawn_action_value),
+                metric: Some("time_to_kill".to_string()),
+                metric_value: ttk.time_to_kill,
+                ..Default::default()
+            });
+        }
+
+        for death in &battle_context.avatar_deaths {
+            all_data.push(ComprehensiveData {
+                data_type: "avatar_death".to_string(),
+                character_name: battle_context.entity_name(&Entity {
+                    uid: death.avatar_id,
+                    team: Team::Player,
+                }),
+                character_id: death.avatar_id,
+                wave: Some(death.wave),
+                action_value: Some(death.action_value),
+                metric: Some("downtime".to_string()),
+                metric_value: Some(death.downtime(battle_context.action_value)),
+                ..Default::default()
             });
         }
 
+        for change in &battle_context.lineup_changes {
+            all_data.push(ComprehensiveData {
+                data_type: "lineup_change".to_string(),
+                character_name: change.name.clone(),
+                character_id: change.avatar_id,
+                turn_battle_id: Some(change.turn_battle_id),
+                wave: Some(change.wave),
+                action_value: Some(change.action_value),
+                metric: Some(format!("{:?}", change.kind)),
+                ..Default::default()
+            });
+        }
+
+        for summon in &battle_context.summons {
+            all_data.push(ComprehensiveData {
+                data_type: "summon".to_string(),
+                character_name: summon.name.clone(),
+                character_id: summon.id,
+                total_damage: Some(summon.damage),
+                damage_percentage: (total_damage > 0.0).then(|| summon.damage / total_damage * 100.0),
+                metric: Some("owner_id".to_string()),
+                metric_value: Some(summon.owner_id as f64),
+                ..Default::default()
+            });
+        }
+
+        for check in battle_context
+            .hit_checks(&SKILL_MULTIPLIERS)
+            .into_iter()
+            .filter(|check| check.flagged)
+        {
+            all_data.push(ComprehensiveData {
+                data_type: "flagged_hit".to_string(),
+                character_name: battle_context.entity_name(&check.hit.attacker),
+                character_id: check.hit.attacker.uid,
+                action_value: Some(check.hit.action_value),
+                skill_name: check.hit.skill_name.clone(),
+                skill_type: Some(check.hit.damage_type as u32),
+                skill_damage: Some(check.hit.damage),
+                metric: Some("deviation".to_string()),
+                metric_value: check.deviation,
+                ..Default::default()
+            });
+        }
+
+        for avatar in &battle_context.avatar_lineup {
+            let Some(build) = &avatar.build else {
+                continue;
+            };
+            let mut metrics = vec![
+                ("level".to_string(), build.level as f64),
+                ("eidolon".to_string(), build.eidolon as f64),
+            ];
+            if let Some(light_cone) = &build.light_cone {
+                metrics.push(("light_cone_id".to_string(), light_cone.id as f64));
+                metrics.push(("light_cone_level".to_string(), light_cone.level as f64));
+                metrics.push(("superimposition".to_string(), light_cone.superimposition as f64));
+            }
+            for bonus in build.set_bonuses() {
+                metrics.push((format!("relic_set_{}", bonus.set_id), bonus.pieces as f64));
+            }
+            for (metric, value) in metrics {
+                all_data.push(ComprehensiveData {
+                    data_type: "avatar_build".to_string(),
+                    character_name: avatar.name.clone(),
+                    character_id: avatar.id,
+                    metric: Some(metric),
+                    metric_value: Some(value),
+                    ..Default::default()
+                });
+            }
+        }
+
+        if let Some(mode_metrics) = battle_context.mode_metrics() {
+            for (metric, value) in mode_metrics.values() {
+                all_data.push(ComprehensiveData {
+                    data_type: "mode_metric".to_string(),
+                    metric: Some(metric.to_string()),
+                    metric_value: Some(value),
+                    ..Default::default()
+                });
+            }
+        }
+
+        if let Some(run) = &battle_context.run {
+            for run_half in &run.halves {
+                for (metric, value) in run_half.totals().values() {
+                    all_data.push(ComprehensiveData {
+                        data_type: "run_half".to_string(),
+                        half: Some(run_half.half),
+                        metric: Some(metric.to_string()),
+                        metric_value: Some(value),
+                        ..Default::default()
+                    });
+                }
+            }
+
+            for (metric, value) in run.totals().values() {
+                all_data.push(ComprehensiveData {
+                    data_type: "run_total".to_string(),
+                    metric: Some(metric.to_string()),
+                    metric_value: Some(value),
+                    ..Default::default()
+                });
+            }
+        }
+
         all_data
     }
 
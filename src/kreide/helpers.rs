@@ -9,10 +9,11 @@ use crate::{
         il2cpp::native::{Il2CppObject, RuntimeType},
         types::{
             RPG_Client_AvatarData, RPG_Client_GlobalVars, RPG_Client_ModuleManager,
-            RPG_Client_UIGameEntityUtils, RPG_GameCore_MonsterDataComponent, RPG_GameCore_ServantDataComponent,
+            RPG_Client_UIGameEntityUtils, RPG_GameCore_LineUpCharacter, RPG_GameCore_LineUpRelic,
+            RPG_GameCore_MonsterDataComponent, RPG_GameCore_ServantDataComponent,
         },
     },
-    models::misc::{Avatar, Skill},
+    models::misc::{Avatar, AvatarBuild, Element, LightCone, Relic, Skill},
 };
 use anyhow::{anyhow, Context, Result};
 use function_name::named;
@@ -62,6 +63,57 @@ pub unsafe fn get_avatar_from_id(avatar_id: u32) -> Result<Avatar> {
     Ok(Avatar {
         id: avatar_id,
         name: avatar_name.to_string(),
+        build: None,
+    })
+}
+
+#[named]
+pub unsafe fn get_avatar_element(avatar_id: u32) -> Result<Element> {
+    log::debug!(function_name!());
+
+    let avatar_data = get_avatar_data_from_id(avatar_id)
+        .context(format!("AvatarData with id {avatar_id} was null"))?;
+    let damage_type = avatar_data.get_damage_type()?;
+    Element::from_damage_type(damage_type)
+        .with_context(|| format!("Unknown damage type {damage_type} of avatar {avatar_id}"))
+}
+
+#[named]
+pub unsafe fn get_avatar_build(character: RPG_GameCore_LineUpCharacter) -> Result<AvatarBuild> {
+    log::debug!(function_name!());
+
+    if character.is_null() {
+        return Err(anyhow!("LineUpCharacter was null"));
+    }
+
+    // Avatars without a light cone have an EquipmentID of 0
+    let equipment_id = character.EquipmentID()?;
+    let light_cone = if equipment_id != 0 {
+        Some(LightCone {
+            id: equipment_id,
+            level: character.EquipmentLevel()?,
+            superimposition: character.EquipmentRank()?,
+        })
+    } else {
+        None
+    };
+
+    let relic_list = character.RelicList()?;
+    let relics = if relic_list.0 == 0 {
+        Vec::new()
+    } else {
+        relic_list
+            .to_vec::<RPG_GameCore_LineUpRelic>()
+            .into_iter()
+            .map(|relic| Ok(Relic::new(relic.RelicID()?, relic.Level()?, relic.MainAffixID()?)))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    Ok(AvatarBuild {
+        level: character.CharacterLevel()?,
+        eidolon: character.CharacterRank()?,
+        light_cone,
+        relics,
     })
 }
 
@@ -109,6 +161,7 @@ pub unsafe fn get_avatar_from_entity(entity: RPG_GameCore_GameEntity) -> Result<
     Ok(Avatar {
         id,
         name: name.to_string(),
+        build: None,
     })
 }
 
@@ -151,6 +204,7 @@ pub unsafe fn get_monster_from_entity(entity: RPG_GameCore_GameEntity) -> Result
     Ok(Avatar {
         id: monster_id,
         name: get_textmap_content(&monster_name)?.to_string(),
+        build: None,
     })
 }
 
@@ -172,6 +226,7 @@ pub unsafe fn get_servant_from_entity(entity: RPG_GameCore_GameEntity) -> Result
     Ok(Avatar {
         id: servant_row.ServantID()?,
         name: get_textmap_content(&servant_row.ServantName()?)?.to_string(),
+        build: None,
     })
 }
 
@@ -288,6 +288,9 @@ impl RPG_GameCore_MonsterRowData {
 
     cs_method!(pub get_level, "get_Level", &[], u32, (), self);
 
+    // Returns RPG.GameCore.MonsterRank
+    cs_method!(pub get_rank, "get_Rank", &[], i32, (), self);
+
     // RPG.GameCore.MonsterRowData -> Type: MonsterRow | Name: _Row | Offset: 0xA8
     cs_field!(_Row, "_Row", self, |v| -> RPG_GameCore_MonsterRow {
         RPG_GameCore_MonsterRow(v.0)
@@ -310,6 +313,9 @@ impl RPG_Client_AvatarData {
     cs_class!("RPG.Client.AvatarData");
 
     cs_property!(pub avatarname, "get_AvatarName", Il2CppString, self);
+
+    // Returns RPG.GameCore.DamageType
+    cs_method!(pub get_damage_type, "get_DamageType", &[], i32, (), self);
 }
 
 #[repr(transparent)]
@@ -326,6 +332,14 @@ impl RPG_GameCore_MonsterRow {
         self,
         |v| -> RPG_Client_TextID_Boxed { RPG_Client_TextID_Boxed(v.0) }
     );
+
+    // RPG.GameCore.MonsterRow -> Type: uint | Name: EliteGroup
+    cs_field!(EliteGroup, "EliteGroup", self, |v| -> u32 { v.unbox::<u32>() });
+
+    // RPG.GameCore.MonsterRow -> Type: DamageType[] | Name: StanceWeakList
+    cs_field!(StanceWeakList, "StanceWeakList", self, |v| -> Il2CppArray {
+        Il2CppArray(v.0)
+    });
 }
 
 #[repr(transparent)]
@@ -738,6 +752,51 @@ impl RPG_GameCore_LineUpCharacter {
     cs_field!(CharacterID, "CharacterID", self, |v| -> u32 {
         v.unbox::<u32>()
     });
+
+    // RPG.GameCore.LineUpCharacter -> Type: uint | Name: CharacterLevel
+    cs_field!(CharacterLevel, "CharacterLevel", self, |v| -> u32 {
+        v.unbox::<u32>()
+    });
+
+    // RPG.GameCore.LineUpCharacter -> Type: uint | Name: CharacterRank
+    cs_field!(CharacterRank, "CharacterRank", self, |v| -> u32 {
+        v.unbox::<u32>()
+    });
+
+    // RPG.GameCore.LineUpCharacter -> Type: uint | Name: EquipmentID
+    cs_field!(EquipmentID, "EquipmentID", self, |v| -> u32 {
+        v.unbox::<u32>()
+    });
+
+    // RPG.GameCore.LineUpCharacter -> Type: uint | Name: EquipmentLevel
+    cs_field!(EquipmentLevel, "EquipmentLevel", self, |v| -> u32 {
+        v.unbox::<u32>()
+    });
+
+    // RPG.GameCore.LineUpCharacter -> Type: uint | Name: EquipmentRank
+    cs_field!(EquipmentRank, "EquipmentRank", self, |v| -> u32 {
+        v.unbox::<u32>()
+    });
+
+    // RPG.GameCore.LineUpCharacter -> Type: List<LineUpRelic> | Name: RelicList
+    cs_field!(RelicList, "RelicList", self, |v| -> List { List(v.0) });
+}
+
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+// RPG.GameCore.LineUpRelic
+pub struct RPG_GameCore_LineUpRelic(pub usize);
+impl RPG_GameCore_LineUpRelic {
+    cs_class!("RPG.GameCore.LineUpRelic");
+
+    // RPG.GameCore.LineUpRelic -> Type: uint | Name: RelicID
+    cs_field!(RelicID, "RelicID", self, |v| -> u32 { v.unbox::<u32>() });
+
+    // RPG.GameCore.LineUpRelic -> Type: uint | Name: Level
+    cs_field!(Level, "Level", self, |v| -> u32 { v.unbox::<u32>() });
+
+    // RPG.GameCore.LineUpRelic -> Type: uint | Name: MainAffixID
+    cs_field!(MainAffixID, "MainAffixID", self, |v| -> u32 { v.unbox::<u32>() });
 }
 
 #[repr(transparent)]
@@ -4,6 +4,7 @@
 #[macro_use]
 extern crate rust_i18n;
 
+mod analysis;
 mod battle;
 mod entry;
 mod export;
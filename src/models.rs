@@ -1,3 +1,4 @@
+pub mod commands;
 pub mod events;
 pub mod misc;
 pub mod packets;
\ No newline at end of file
@@ -0,0 +1,120 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    misc::{Entity, Stat},
+    packets::{Encoding, Packet},
+};
+
+// Inbound requests sent by clients through the `Command` socket event
+// e.g. {"type": "ToggleWindow", "window": "DamageBars"}
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum Command {
+    // Written to the export folder configured in the overlay
+    Export {
+        format: ExportFormat,
+    },
+    ResetTallies,
+    MarkSegment {
+        label: String,
+    },
+    ToggleWindow {
+        window: OverlayWindow,
+        // Flips the current visibility when omitted
+        visible: Option<bool>,
+    },
+    QueryHistory,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum OverlayWindow {
+    Menu,
+    Console,
+    DamageDistribution,
+    CharacterLegend,
+    DamageBars,
+    RealTimeDamage,
+    BattleMetrics,
+    EnemyStats,
+    ModeMetrics,
+    ActionTimeline,
+    SpeedAnalysis,
+    RotationAnalysis,
+    EnabledDamageDistribution,
+    Export,
+}
+
+// Sent as the Socket.IO auth payload when connecting
+// e.g. {"events": ["OnDamage", "OnStatChange"], "stats": ["HP"], "entities": [{"uid": 1310, "team": "Player"}], "encoding": "msgpack"}
+// An empty list places no restriction
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Subscription {
+    // Packet names
+    pub events: Vec<String>,
+    // Stat kinds delivered through `OnStatChange`
+    pub stats: Vec<String>,
+    // Entities as {"uid": 1310, "team": "Player"}, avatars and enemies can share uids
+    pub entities: Vec<Entity>,
+    // How packets are encoded for this client
+    pub encoding: Encoding,
+}
+
+impl Subscription {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(event) = self
+            .events
+            .iter()
+            .find(|event| !Packet::NAMES.contains(&event.as_str()))
+        {
+            bail!("Cannot subscribe to unknown event {event}");
+        }
+        if let Some(stat) = self
+            .stats
+            .iter()
+            .find(|stat| !Stat::KINDS.contains(&stat.as_str()))
+        {
+            bail!("Cannot subscribe to unknown stat {stat}");
+        }
+        Ok(())
+    }
+
+    pub fn accepts(&self, packet: &Packet) -> bool {
+        if !self.events.is_empty() && !self.events.iter().any(|event| event == packet.name()) {
+            return false;
+        }
+
+        if let Packet::OnStatChange { stat, .. } = packet {
+            if !self.stats.is_empty() && !self.stats.iter().any(|kind| kind == stat.kind()) {
+                return false;
+            }
+        }
+
+        // Entity equality ignores the team, so it's compared explicitly
+        match packet.entities() {
+            Some(entities) if !self.entities.is_empty() => entities.iter().any(|entity| {
+                self.entities.iter().any(|subscribed| {
+                    subscribed.uid == entity.uid && subscribed.team == entity.team
+                })
+            }),
+            _ => true,
+        }
+    }
+}
@@ -1,5 +1,6 @@
+use std::hash::{Hash, Hasher};
 
-use super::misc::{Avatar, Enemy, Entity, Skill, Stat, Team};
+use super::misc::{Avatar, Element, Enemy, Entity, Skill, Stat, Team};
 
 pub enum Event {
     OnBattleBegin(OnBattleBeginEvent),
@@ -38,7 +39,9 @@ pub struct OnTurnBeginEvent {
 
 pub struct OnUseSkillEvent {
     pub avatar: Entity,
-    pub skill: Skill
+    pub skill: Skill,
+    // Out of turn automatic skill, e.g. a follow-up or counter
+    pub insert: bool
 }
 
 pub struct OnSetLineupEvent {
@@ -47,6 +50,14 @@ pub struct OnSetLineupEvent {
 
 pub struct OnDamageEvent {
     pub attacker: Entity,
+    // Resolved attacker, registered if it joined after the lineup was set
+    pub avatar: Avatar,
+    // None when the defender isn't an enemy
+    pub defender: Option<Entity>,
+    // Servant that dealt the damage on behalf of the attacker
+    pub summon: Option<Avatar>,
+    // Element of the attacker, used for element specific DMG% and RES
+    pub element: Option<Element>,
     pub damage: f64,
     pub damage_type: isize,
     pub overkill_damage: f64
@@ -64,7 +75,9 @@ pub struct OnStatChangeEvent {
 
 pub struct OnUpdateTeamFormationEvent {
     pub entities: Vec<Entity>,
-    pub team: Team
+    pub team: Team,
+    // Avatars on the field, empty for the enemy team
+    pub avatars: Vec<Avatar>
 }
 
 pub struct OnInitializeEnemyEvent {
@@ -76,3 +89,11 @@ impl PartialEq for Entity {
         self.uid == other.uid
     }
 }
+
+impl Eq for Entity {}
+
+impl Hash for Entity {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uid.hash(state);
+    }
+}
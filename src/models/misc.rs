@@ -9,6 +9,70 @@ use serde::{
 pub struct Avatar {
     pub id: u32,
     pub name: String,
+    // Only set for lineup avatars
+    #[serde(default)]
+    pub build: Option<AvatarBuild>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AvatarBuild {
+    pub level: u32,
+    pub eidolon: u32,
+    pub light_cone: Option<LightCone>,
+    pub relics: Vec<Relic>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LightCone {
+    pub id: u32,
+    pub level: u32,
+    pub superimposition: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Relic {
+    pub id: u32,
+    pub set_id: u32,
+    pub slot: u32,
+    pub level: u32,
+    pub main_affix_id: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RelicSetBonus {
+    pub set_id: u32,
+    pub pieces: u32,
+}
+
+impl Relic {
+    // Relic ids are laid out as <rarity><set id><slot>, e.g. 61011
+    pub fn new(id: u32, level: u32, main_affix_id: u32) -> Self {
+        Self {
+            id,
+            set_id: id / 10 % 1000,
+            slot: id % 10,
+            level,
+            main_affix_id,
+        }
+    }
+}
+
+impl AvatarBuild {
+    // Sets with at least two pieces equipped
+    pub fn set_bonuses(&self) -> Vec<RelicSetBonus> {
+        let mut bonuses: Vec<RelicSetBonus> = Vec::new();
+        for relic in &self.relics {
+            match bonuses.iter_mut().find(|bonus| bonus.set_id == relic.set_id) {
+                Some(bonus) => bonus.pieces += 1,
+                None => bonuses.push(RelicSetBonus {
+                    set_id: relic.set_id,
+                    pieces: 1,
+                }),
+            }
+        }
+        bonuses.retain(|bonus| bonus.pieces >= 2);
+        bonuses
+    }
 }
 
 
@@ -18,6 +82,89 @@ pub struct Enemy {
     pub uid: u32,
     pub name: String,
     pub base_stats: Stats,
+    #[serde(default)]
+    pub weaknesses: Vec<Element>,
+    #[serde(default)]
+    pub resistances: Vec<ElementResistance>,
+    #[serde(default)]
+    pub max_toughness: f64,
+    #[serde(default)]
+    pub rank: EnemyRank,
+    #[serde(default)]
+    pub elite_group: u32,
+    // Set by the battle context when the enemy joins
+    #[serde(default)]
+    pub wave: u32,
+    #[serde(default)]
+    pub position: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Element {
+    Physical,
+    Fire,
+    Ice,
+    Lightning,
+    Wind,
+    Quantum,
+    Imaginary,
+}
+
+impl Element {
+    pub const ALL: [Element; 7] = [
+        Element::Physical,
+        Element::Fire,
+        Element::Ice,
+        Element::Lightning,
+        Element::Wind,
+        Element::Quantum,
+        Element::Imaginary,
+    ];
+
+    // From RPG.GameCore.DamageType
+    pub fn from_damage_type(damage_type: i32) -> Option<Self> {
+        match damage_type {
+            1 => Some(Element::Physical),
+            2 => Some(Element::Fire),
+            3 => Some(Element::Ice),
+            4 => Some(Element::Lightning),
+            5 => Some(Element::Wind),
+            6 => Some(Element::Quantum),
+            7 => Some(Element::Imaginary),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ElementResistance {
+    pub element: Element,
+    pub value: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub enum EnemyRank {
+    #[default]
+    Normal,
+    Elite,
+    Boss,
+}
+
+impl EnemyRank {
+    // From RPG.GameCore.MonsterRank, Minion and MinionLv2 are normal enemies
+    pub fn from_monster_rank(rank: i32) -> Self {
+        match rank {
+            3 => EnemyRank::Elite,
+            4 | 5 => EnemyRank::Boss,
+            _ => EnemyRank::Normal,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -50,211 +197,241 @@ pub struct Entity {
     pub team: Team
 }
 
-#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Deserialize, Serialize)]
 pub enum Team {
     Player,
     Enemy
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub enum Stat {
-    MaxHP(f64),
-    BaseHP(f64),
-    HPAddedRatio(f64),
-    HPDelta(f64),
-    HPConvert(f64),
-    DirtyHPDelta(f64),
-    DirtyHPRatio(f64),
-    RallyHP(f64),
-    NegativeHP(f64),
-    HP(f64),
-    MaxSP(f64),
-    CurrentSP(f64),
-    MaxSpecialSP(f64),
-    CurrentSpecialSP(f64),
-    AdditionalBP(f64),
-    Attack(f64),
-    BaseAttack(f64),
-    AttackAddedRatio(f64),
-    AttackDelta(f64),
-    AttackConvert(f64),
-    Defense(f64),
-    BaseDefence(f64),
-    DefenceAddedRatio(f64),
-    DefenceDelta(f64),
-    DefenceConvert(f64),
-    DefenceOverride(f64),
-    Level(f64),
-    Promotion(f64),
-    Rank(f64),
-    Speed(f64),
-    BaseSpeed(f64),
-    SpeedAddedRatio(f64),
-    SpeedDelta(f64),
-    SpeedConvert(f64),
-    SpeedOverride(f64),
-    AV(f64),
-    ActionDelayAddedRatio(f64),
-    ActionDelayAddAttenuation(f64),
-    MaxStance(f64),
-    CurrentStance(f64),
-    Level_AllDamageAddedRatio(f64),
-    AllDamageTypeAddedRatio(f64),
-    AllDamageReduce(f64),
-    DotDamageAddedRatio(f64),
-    FatigueRatio(f64),
-    CriticalChance(f64),
-    CriticalChanceBase(f64),
-    CriticalChanceConvert(f64),
-    CriticalDamage(f64),
-    CriticalDamageBase(f64),
-    CriticalDamageConvert(f64),
-    CriticalResistance(f64),
-    PhysicalAddedRatio(f64),
-    FireAddedRatio(f64),
-    IceAddedRatio(f64),
-    ThunderAddedRatio(f64),
-    QuantumAddedRatio(f64),
-    ImaginaryAddedRatio(f64),
-    WindAddedRatio(f64),
-    PhysicalResistance(f64),
-    FireResistance(f64),
-    IceResistance(f64),
-    ThunderResistance(f64),
-    QuantumResistance(f64),
-    ImaginaryResistance(f64),
-    WindResistance(f64),
-    PhysicalResistanceBase(f64),
-    FireResistanceBase(f64),
-    IceResistanceBase(f64),
-    ThunderResistanceBase(f64),
-    QuantumResistanceBase(f64),
-    ImaginaryResistanceBase(f64),
-    WindResistanceBase(f64),
-    PhysicalResistanceDelta(f64),
-    FireResistanceDelta(f64),
-    IceResistanceDelta(f64),
-    ThunderResistanceDelta(f64),
-    QuantumResistanceDelta(f64),
-    ImaginaryResistanceDelta(f64),
-    WindResistanceDelta(f64),
-    AllDamageTypeResistance(f64),
-    PhysicalPenetrate(f64),
-    FirePenetrate(f64),
-    IcePenetrate(f64),
-    ThunderPenetrate(f64),
-    QuantumPenetrate(f64),
-    ImaginaryPenetrate(f64),
-    WindPenetrate(f64),
-    AllDamageTypePenetrate(f64),
-    PhysicalTakenRatio(f64),
-    FireTakenRatio(f64),
-    IceTakenRatio(f64),
-    ThunderTakenRatio(f64),
-    QuantumTakenRatio(f64),
-    ImaginaryTakenRatio(f64),
-    WindTakenRatio(f64),
-    AllDamageTypeTakenRatio(f64),
-    Monster_DamageTakenRatio(f64),
-    PhysicalAbsorb(f64),
-    FireAbsorb(f64),
-    IceAbsorb(f64),
-    ThunderAbsorb(f64),
-    QuantumAbsorb(f64),
-    ImaginaryAbsorb(f64),
-    WindAbsorb(f64),
-    MinimumFatigueRatio(f64),
-    ForceStanceBreakRatio(f64),
-    StanceBreakAddedRatio(f64),
-    StanceBreakResistance(f64),
-    StanceBreakTakenRatio(f64),
-    PhysicalStanceBreakTakenRatio(f64),
-    FireStanceBreakTakenRatio(f64),
-    IceStanceBreakTakenRatio(f64),
-    ThunderStanceBreakTakenRatio(f64),
-    WindStanceBreakTakenRatio(f64),
-    QuantumStanceBreakTakenRatio(f64),
-    ImaginaryStanceBreakTakenRatio(f64),
-    StanceWeakAddedRatio(f64),
-    StanceDefaultAddedRatio(f64),
-    HealRatio(f64),
-    HealRatioBase(f64),
-    HealRatioConvert(f64),
-    HealTakenRatio(f64),
-    Shield(f64),
-    MaxShield(f64),
-    ShieldAddedRatio(f64),
-    ShieldTakenRatio(f64),
-    StatusProbability(f64),
-    StatusProbabilityBase(f64),
-    StatusProbabilityConvert(f64),
-    StatusResistance(f64),
-    StatusResistanceBase(f64),
-    StatusResistanceConvert(f64),
-    SPRatio(f64),
-    SPRatioBase(f64),
-    SPRatioConvert(f64),
-    SPRatioOverride(f64),
-    BreakDamageAddedRatio(f64),
-    BreakDamageAddedRatioBase(f64),
-    BreakDamageAddedRatioConvert(f64),
-    BreakDamageExtraAddedRatio(f64),
-    PhysicalStanceBreakResistance(f64),
-    FireStanceBreakResistance(f64),
-    IceStanceBreakResistance(f64),
-    ThunderStanceBreakResistance(f64),
-    WindStanceBreakResistance(f64),
-    QuantumStanceBreakResistance(f64),
-    ImaginaryStanceBreakResistance(f64),
-    AggroBase(f64),
-    AggroAddedRatio(f64),
-    AggroDelta(f64),
-    RelicValueExtraAdditionRatio(f64),
-    EquipValueExtraAdditionRatio(f64),
-    EquipExtraRank(f64),
-    AvatarExtraRank(f64),
-    Combo(f64),
-    NormalBattleCount(f64),
-    ExtraAttackAddedRatio1(f64),
-    ExtraAttackAddedRatio2(f64),
-    ExtraAttackAddedRatio3(f64),
-    ExtraAttackAddedRatio4(f64),
-    ExtraDefenceAddedRatio1(f64),
-    ExtraDefenceAddedRatio2(f64),
-    ExtraDefenceAddedRatio3(f64),
-    ExtraDefenceAddedRatio4(f64),
-    ExtraHPAddedRatio1(f64),
-    ExtraHPAddedRatio2(f64),
-    ExtraHPAddedRatio3(f64),
-    ExtraHPAddedRatio4(f64),
-    ExtraHealAddedRatio(f64),
-    ExtraAllDamageTypeAddedRatio1(f64),
-    ExtraAllDamageTypeAddedRatio2(f64),
-    ExtraAllDamageTypeAddedRatio3(f64),
-    ExtraAllDamageTypeAddedRatio4(f64),
-    ExtraAllDamageReduce(f64),
-    ExtraShieldAddedRatio(f64),
-    ExtraSpeedAddedRatio1(f64),
-    ExtraSpeedAddedRatio2(f64),
-    ExtraSpeedAddedRatio3(f64),
-    ExtraSpeedAddedRatio4(f64),
-    ExtraLuckChance(f64),
-    ExtraLuckDamage(f64),
-    ExtraFrontPower(f64),
-    ExtraFrontPowerBase(f64),
-    ExtraFrontPowerAddedRatio1(f64),
-    ExtraFrontPowerAddedRatio2(f64),
-    ExtraBackPower(f64),
-    ExtraBackPowerBase(f64),
-    ExtraBackPowerAddedRatio1(f64),
-    ExtraBackPowerAddedRatio2(f64),
-    ExtraUltraDamageAddedRatio1(f64),
-    ExtraSkillDamageAddedRatio1(f64),
-    ExtraNormalDamageAddedRatio1(f64),
-    ExtraInsertDamageAddedRatio1(f64),
-    ExtraDOTDamageAddedRatio1(f64),
+macro_rules! stats {
+    ($($stat:ident),* $(,)?) => {
+        #[derive(Clone, Debug, Deserialize, Serialize)]
+        pub enum Stat {
+            $(
+                $stat(f64),
+            )*
+        }
+
+        impl Stat {
+            pub const KINDS: &'static [&'static str] = &[$(stringify!($stat)),*];
+
+            pub fn kind(&self) -> &'static str {
+                match self {
+                    $(
+                        Self::$stat(_) => stringify!($stat),
+                    )*
+                }
+            }
+
+            pub fn value(&self) -> f64 {
+                match self {
+                    $(
+                        Self::$stat(value) => *value,
+                    )*
+                }
+            }
+        }
+    };
 }
 
+stats!(
+    MaxHP,
+    BaseHP,
+    HPAddedRatio,
+    HPDelta,
+    HPConvert,
+    DirtyHPDelta,
+    DirtyHPRatio,
+    RallyHP,
+    NegativeHP,
+    HP,
+    MaxSP,
+    CurrentSP,
+    MaxSpecialSP,
+    CurrentSpecialSP,
+    AdditionalBP,
+    Attack,
+    BaseAttack,
+    AttackAddedRatio,
+    AttackDelta,
+    AttackConvert,
+    Defense,
+    BaseDefence,
+    DefenceAddedRatio,
+    DefenceDelta,
+    DefenceConvert,
+    DefenceOverride,
+    Level,
+    Promotion,
+    Rank,
+    Speed,
+    BaseSpeed,
+    SpeedAddedRatio,
+    SpeedDelta,
+    SpeedConvert,
+    SpeedOverride,
+    AV,
+    ActionDelayAddedRatio,
+    ActionDelayAddAttenuation,
+    MaxStance,
+    CurrentStance,
+    Level_AllDamageAddedRatio,
+    AllDamageTypeAddedRatio,
+    AllDamageReduce,
+    DotDamageAddedRatio,
+    FatigueRatio,
+    CriticalChance,
+    CriticalChanceBase,
+    CriticalChanceConvert,
+    CriticalDamage,
+    CriticalDamageBase,
+    CriticalDamageConvert,
+    CriticalResistance,
+    PhysicalAddedRatio,
+    FireAddedRatio,
+    IceAddedRatio,
+    ThunderAddedRatio,
+    QuantumAddedRatio,
+    ImaginaryAddedRatio,
+    WindAddedRatio,
+    PhysicalResistance,
+    FireResistance,
+    IceResistance,
+    ThunderResistance,
+    QuantumResistance,
+    ImaginaryResistance,
+    WindResistance,
+    PhysicalResistanceBase,
+    FireResistanceBase,
+    IceResistanceBase,
+    ThunderResistanceBase,
+    QuantumResistanceBase,
+    ImaginaryResistanceBase,
+    WindResistanceBase,
+    PhysicalResistanceDelta,
+    FireResistanceDelta,
+    IceResistanceDelta,
+    ThunderResistanceDelta,
+    QuantumResistanceDelta,
+    ImaginaryResistanceDelta,
+    WindResistanceDelta,
+    AllDamageTypeResistance,
+    PhysicalPenetrate,
+    FirePenetrate,
+    IcePenetrate,
+    ThunderPenetrate,
+    QuantumPenetrate,
+    ImaginaryPenetrate,
+    WindPenetrate,
+    AllDamageTypePenetrate,
+    PhysicalTakenRatio,
+    FireTakenRatio,
+    IceTakenRatio,
+    ThunderTakenRatio,
+    QuantumTakenRatio,
+    ImaginaryTakenRatio,
+    WindTakenRatio,
+    AllDamageTypeTakenRatio,
+    Monster_DamageTakenRatio,
+    PhysicalAbsorb,
+    FireAbsorb,
+    IceAbsorb,
+    ThunderAbsorb,
+    QuantumAbsorb,
+    ImaginaryAbsorb,
+    WindAbsorb,
+    MinimumFatigueRatio,
+    ForceStanceBreakRatio,
+    StanceBreakAddedRatio,
+    StanceBreakResistance,
+    StanceBreakTakenRatio,
+    PhysicalStanceBreakTakenRatio,
+    FireStanceBreakTakenRatio,
+    IceStanceBreakTakenRatio,
+    ThunderStanceBreakTakenRatio,
+    WindStanceBreakTakenRatio,
+    QuantumStanceBreakTakenRatio,
+    ImaginaryStanceBreakTakenRatio,
+    StanceWeakAddedRatio,
+    StanceDefaultAddedRatio,
+    HealRatio,
+    HealRatioBase,
+    HealRatioConvert,
+    HealTakenRatio,
+    Shield,
+    MaxShield,
+    ShieldAddedRatio,
+    ShieldTakenRatio,
+    StatusProbability,
+    StatusProbabilityBase,
+    StatusProbabilityConvert,
+    StatusResistance,
+    StatusResistanceBase,
+    StatusResistanceConvert,
+    SPRatio,
+    SPRatioBase,
+    SPRatioConvert,
+    SPRatioOverride,
+    BreakDamageAddedRatio,
+    BreakDamageAddedRatioBase,
+    BreakDamageAddedRatioConvert,
+    BreakDamageExtraAddedRatio,
+    PhysicalStanceBreakResistance,
+    FireStanceBreakResistance,
+    IceStanceBreakResistance,
+    ThunderStanceBreakResistance,
+    WindStanceBreakResistance,
+    QuantumStanceBreakResistance,
+    ImaginaryStanceBreakResistance,
+    AggroBase,
+    AggroAddedRatio,
+    AggroDelta,
+    RelicValueExtraAdditionRatio,
+    EquipValueExtraAdditionRatio,
+    EquipExtraRank,
+    AvatarExtraRank,
+    Combo,
+    NormalBattleCount,
+    ExtraAttackAddedRatio1,
+    ExtraAttackAddedRatio2,
+    ExtraAttackAddedRatio3,
+    ExtraAttackAddedRatio4,
+    ExtraDefenceAddedRatio1,
+    ExtraDefenceAddedRatio2,
+    ExtraDefenceAddedRatio3,
+    ExtraDefenceAddedRatio4,
+    ExtraHPAddedRatio1,
+    ExtraHPAddedRatio2,
+    ExtraHPAddedRatio3,
+    ExtraHPAddedRatio4,
+    ExtraHealAddedRatio,
+    ExtraAllDamageTypeAddedRatio1,
+    ExtraAllDamageTypeAddedRatio2,
+    ExtraAllDamageTypeAddedRatio3,
+    ExtraAllDamageTypeAddedRatio4,
+    ExtraAllDamageReduce,
+    ExtraShieldAddedRatio,
+    ExtraSpeedAddedRatio1,
+    ExtraSpeedAddedRatio2,
+    ExtraSpeedAddedRatio3,
+    ExtraSpeedAddedRatio4,
+    ExtraLuckChance,
+    ExtraLuckDamage,
+    ExtraFrontPower,
+    ExtraFrontPowerBase,
+    ExtraFrontPowerAddedRatio1,
+    ExtraFrontPowerAddedRatio2,
+    ExtraBackPower,
+    ExtraBackPowerBase,
+    ExtraBackPowerAddedRatio1,
+    ExtraBackPowerAddedRatio2,
+    ExtraUltraDamageAddedRatio1,
+    ExtraSkillDamageAddedRatio1,
+    ExtraNormalDamageAddedRatio1,
+    ExtraInsertDamageAddedRatio1,
+    ExtraDOTDamageAddedRatio1,
+);
+
 impl fmt::Display for Avatar {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.name)
@@ -283,4 +460,40 @@ pub struct TurnInfo {
     pub wave: u32,
     pub avatars_turn_damage: Vec<f64>,
     pub total_damage: f64,
-}
\ No newline at end of file
+    // Entity whose turn it was
+    #[serde(default)]
+    pub turn_owner: Option<Entity>,
+}
+
+// A user-placed marker splitting the battle into segments
+// Damage is the running tally at the time the marker was placed
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SegmentMarker {
+    pub label: String,
+    pub action_value: f64,
+    pub turn_count: usize,
+    pub avatars_damage: Vec<f64>,
+    pub total_damage: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum LineupChangeKind {
+    // Wasn't in the lineup the battle started with, e.g. trial, support or
+    // Divergent Universe characters
+    Joined,
+    // Dropped out of the formation, e.g. downed or swapped out
+    Left,
+    // Back in the formation after leaving it, e.g. revived or swapped back in
+    Returned,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineupChange {
+    pub avatar_id: u32,
+    pub name: String,
+    pub kind: LineupChangeKind,
+    pub action_value: f64,
+    pub wave: u32,
+    pub turn_battle_id: u32,
+}
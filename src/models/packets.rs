@@ -1,6 +1,19 @@
-use serde::Serialize;
-
-use super::misc::{Avatar, Enemy, Entity, Skill, Stat, Team, TurnInfo};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    commands::{ExportFormat, OverlayWindow},
+    misc::{Avatar, Enemy, Entity, LineupChange, SegmentMarker, Skill, Stat, Team, TurnInfo},
+};
+
+// Negotiated per client when connecting
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum Encoding {
+    #[default]
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "msgpack")]
+    MessagePack,
+}
 
 macro_rules! packet {
     ($(
@@ -15,6 +28,8 @@ macro_rules! packet {
         }
 
         impl Packet {
+            pub const NAMES: &'static [&'static str] = &[$(stringify!($variant_name)),*];
+
             pub fn name(&self) -> &'static str {
                 match self {
                     $(
@@ -30,6 +45,11 @@ macro_rules! packet {
                     )*
                 }
             }
+
+            // Same structure as `payload`, with struct fields kept as named map keys
+            pub fn payload_message_pack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+                rmp_serde::to_vec_named(&self)
+            }
         }
     };
 }
@@ -55,6 +75,8 @@ packet!(
 
     OnDamage {
         attacker: Entity,
+        defender: Option<Entity>,
+        summon: Option<Avatar>,
         damage: f64,
         damage_type: isize
     }
@@ -100,6 +122,11 @@ packet!(
         enemy: Enemy
     }
 
+    OnLineupChange {
+        change: LineupChange,
+        avatars: Vec<Avatar>
+    }
+
     OnBattleEnd {
         avatars: Vec<Avatar>,
         turn_history: Vec<TurnInfo>,
@@ -111,4 +138,59 @@ packet!(
         wave: u32,
         stage_id: u32
     }
+
+    // Command responses
+    Exported {
+        format: ExportFormat,
+        path: String
+    }
+
+    TalliesReset {}
+
+    SegmentMarked {
+        segment: SegmentMarker
+    }
+
+    WindowToggled {
+        window: OverlayWindow,
+        visible: bool
+    }
+
+    History {
+        avatars: Vec<Avatar>,
+        turn_history: Vec<TurnInfo>,
+        av_history: Vec<TurnInfo>,
+        segment_markers: Vec<SegmentMarker>,
+        turn_count: usize,
+        total_damage: f64,
+        action_value: f64
+    }
 );
+
+impl Packet {
+    // Entities a packet is about, `None` if it isn't tied to any entity
+    pub fn entities(&self) -> Option<Vec<Entity>> {
+        match self {
+            Self::OnDamage {
+                attacker, defender, ..
+            } => Some(std::iter::once(attacker).chain(defender).cloned().collect()),
+            Self::OnTurnBegin { turn_owner, .. } => Some(turn_owner.iter().cloned().collect()),
+            Self::OnEntityDefeated {
+                killer,
+                entity_defeated,
+            } => Some(vec![killer.clone(), entity_defeated.clone()]),
+            Self::OnUseSkill { avatar, .. } => Some(vec![avatar.clone()]),
+            Self::OnStatChange { entity, .. } => Some(vec![entity.clone()]),
+            Self::OnUpdateTeamFormation { entities, .. } => Some(entities.clone()),
+            Self::OnInitializeEnemy { enemy } => Some(vec![Entity {
+                uid: enemy.uid,
+                team: Team::Enemy,
+            }]),
+            Self::OnLineupChange { change, .. } => Some(vec![Entity {
+                uid: change.avatar_id,
+                team: Team::Player,
+            }]),
+            _ => None,
+        }
+    }
+}
@@ -1,14 +1,49 @@
-use axum::{Router, response::Redirect, routing::get};
-use socketioxide::{SocketIo, extract::SocketRef};
-use std::{net::SocketAddr, str::FromStr, sync::OnceLock};
+use axum::{
+    Json, Router,
+    http::{HeaderValue, header::ORIGIN},
+    response::Html,
+    routing::get,
+};
+use serde::{Serialize, Serializer};
+use socketioxide::{
+    SocketIo,
+    extract::{Data, SocketRef, TryData},
+    layer::SocketIoLayer,
+    socket::Sid,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    str::FromStr,
+    sync::{LazyLock, Mutex, OnceLock},
+    time::Duration,
+};
+use tokio::sync::{Notify, oneshot};
 use tower::ServiceBuilder;
-use tower_http::cors::{Any, CorsLayer};
-use anyhow::anyhow;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use anyhow::{Result, anyhow};
 
-use crate::{RUNTIME, models::packets::Packet};
+use crate::{
+    RUNTIME,
+    battle::BattleContext,
+    models::{
+        commands::{Command, Subscription},
+        misc::{Avatar, Team, TurnInfo},
+        packets::{Encoding, Packet},
+    },
+};
 
 const SERVER_ADDR: &str = "127.0.0.1:1305";
 
+// How long a client waits on the overlay before the command is reported as failed
+const OVERLAY_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Packets waiting to be dispatched past this point push out the oldest ones
+const BROADCAST_QUEUE_CAPACITY: usize = 4096;
+
+// Bundled in the binary so the live view works without the external site
+const DASHBOARD: &str = include_str!("../assets/dashboard.html");
+
 static SOCKET_IO: OnceLock<SocketIo> = OnceLock::new();
 
 pub fn start_server() {
@@ -20,17 +55,9 @@ pub fn start_server() {
             log::error!("{e}");
             panic!("{e}");
         }
+        tokio::spawn(dispatch_broadcasts());
 
-        let app = Router::new().route("/", get(redirect_to_new_page)).layer(
-            ServiceBuilder::new()
-                .layer(
-                    CorsLayer::new()
-                        .allow_origin(Any)
-                        .allow_methods(Any)
-                        .allow_headers(Any),
-                )
-                .layer(layer),
-        );
+        let app = router(layer);
 
         // HTTP
         axum_server::bind(SocketAddr::from_str(SERVER_ADDR).unwrap_or_else(|e| {
@@ -46,27 +73,843 @@ pub fn start_server() {
     });
 }
 
-async fn redirect_to_new_page() -> Redirect {
-    Redirect::temporary("https://sranalysis.kain.id.vn")
+fn router(layer: SocketIoLayer) -> Router {
+    Router::new()
+        .route("/", get(dashboard))
+        .route("/api/snapshot", get(snapshot))
+        .route("/api/broadcast", get(broadcast_stats))
+        .layer(
+            ServiceBuilder::new()
+                .layer(
+                    CorsLayer::new()
+                        .allow_origin(AllowOrigin::predicate(|origin, _| is_local_origin(origin)))
+                        .allow_methods(Any)
+                        .allow_headers(Any),
+                )
+                .layer(layer),
+        )
+}
+
+// Only pages served from this machine may talk to the server, so a website opened in
+// the browser can't read battle data or send commands
+fn is_local_origin(origin: &HeaderValue) -> bool {
+    let Some((_, host)) = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| origin.split_once("://"))
+    else {
+        return false;
+    };
+    ["localhost", "127.0.0.1", "[::1]"].iter().any(|local| {
+        host.strip_prefix(local)
+            .is_some_and(|port| port.is_empty() || port.starts_with(':'))
+    })
+}
+
+async fn dashboard() -> Html<&'static str> {
+    Html(DASHBOARD)
+}
+
+// Current battle state so the dashboard can catch up when opened mid-battle
+#[derive(Serialize)]
+struct Snapshot {
+    avatars: Vec<Avatar>,
+    damages: Vec<f64>,
+    turn_history: Vec<TurnInfo>,
+    action_value: f64,
+    total_damage: f64,
+    wave: u32,
+    cycle: u32,
+    stage_id: u32,
+}
+
+async fn snapshot() -> Json<Snapshot> {
+    let battle_context = BattleContext::get_instance();
+    Json(Snapshot {
+        avatars: battle_context.avatar_lineup.clone(),
+        damages: battle_context.real_time_damages.clone(),
+        turn_history: battle_context.turn_history.clone(),
+        action_value: battle_context.action_value,
+        total_damage: battle_context.total_damage,
+        wave: battle_context.wave,
+        cycle: battle_context.cycle,
+        stage_id: battle_context.stage_id,
+    })
+}
+
+#[derive(Serialize)]
+struct ClientStats {
+    id: String,
+    dropped: u64,
 }
 
-fn on_connect(socket: SocketRef) {
+#[derive(Serialize)]
+struct BroadcastStats {
+    queued: usize,
+    dropped: u64,
+    clients: Vec<ClientStats>,
+}
+
+async fn broadcast_stats() -> Json<BroadcastStats> {
+    let (queued, dropped) = BROADCAST_QUEUE
+        .lock()
+        .map(|queue| (queue.len(), queue.dropped))
+        .unwrap_or_default();
+    let clients = CLIENTS
+        .lock()
+        .map(|clients| {
+            clients
+                .iter()
+                .map(|(sid, client)| ClientStats {
+                    id: sid.to_string(),
+                    dropped: client.dropped,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Json(BroadcastStats {
+        queued,
+        dropped,
+        clients,
+    })
+}
+
+struct Client {
+    socket: SocketRef,
+    subscription: Subscription,
+    // Packets this client missed because its buffer was full or it went away
+    dropped: u64,
+}
+
+static CLIENTS: LazyLock<Mutex<HashMap<Sid, Client>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn on_connect(socket: SocketRef, TryData(auth): TryData<serde_json::Value>) {
+    // Browsers always send an origin, other clients don't have to
+    if let Some(origin) = socket.req_parts().headers.get(ORIGIN) {
+        if !is_local_origin(origin) {
+            log::warn!("Rejected client {} from origin {:?}", socket.id, origin);
+            socket.disconnect().ok();
+            return;
+        }
+    }
+
+    socket.on("Command", on_command);
+    socket.on_disconnect(on_disconnect);
+
+    // Clients that don't send a subscription receive everything as JSON
+    let subscription = match auth {
+        Ok(serde_json::Value::Null) | Err(_) => Ok(Subscription::default()),
+        Ok(auth) => serde_json::from_value::<Subscription>(auth)
+            .map_err(|e| anyhow!("Invalid subscription: {e}")),
+    };
+    // Unknown names never match, so the rest of the subscription still applies
+    let (subscription, error) = match subscription {
+        Ok(subscription) => {
+            let error = subscription.validate().err();
+            (subscription, error)
+        }
+        Err(e) => (Subscription::default(), Some(e)),
+    };
+    let encoding = subscription.encoding;
+
+    if let Ok(mut clients) = CLIENTS.lock() {
+        clients.insert(
+            socket.id,
+            Client {
+                socket: socket.clone(),
+                subscription,
+                dropped: 0,
+            },
+        );
+    }
+
     let packet = Packet::Connected {
         version: env!("CARGO_PKG_VERSION").to_string(),
     };
-    socket.emit(&packet.name(), &packet.payload()).ok();
+    EncodedPacket::new(&packet).emit(&socket, encoding).ok();
+
+    if let Some(e) = error {
+        emit_error(&socket, e);
+    }
 }
 
-pub fn broadcast(packet: Packet) {
-    RUNTIME.spawn(async move {
-        if let Some(io) = SOCKET_IO.get() {
-            io.broadcast()
-                .emit(&packet.name(), &packet.payload())
+fn on_disconnect(socket: SocketRef) {
+    if let Ok(mut clients) = CLIENTS.lock() {
+        if let Some(client) = clients.remove(&socket.id) {
+            if client.dropped > 0 {
+                log::warn!(
+                    "Client {} disconnected after missing {} packets",
+                    socket.id,
+                    client.dropped
+                );
+            }
+        }
+    }
+}
+
+// Commands that depend on overlay state (export settings, window visibility)
+// are handed to the overlay and answered on its next frame
+pub struct OverlayRequest {
+    pub command: Command,
+    responder: oneshot::Sender<Result<Packet>>,
+}
+
+impl OverlayRequest {
+    pub fn respond(self, result: Result<Packet>) {
+        // The client may have already timed out
+        self.responder.send(result).ok();
+    }
+}
+
+static OVERLAY_REQUESTS: LazyLock<Mutex<Vec<OverlayRequest>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+pub fn take_overlay_requests() -> Vec<OverlayRequest> {
+    OVERLAY_REQUESTS
+        .lock()
+        .map(|mut requests| std::mem::take(&mut *requests))
+        .unwrap_or_default()
+}
+
+async fn on_command(socket: SocketRef, Data(data): Data<serde_json::Value>) {
+    let result = match serde_json::from_value::<Command>(data) {
+        Ok(command) => handle_command(command).await,
+        Err(e) => Err(anyhow!("Invalid command: {e}")),
+    };
+
+    match result {
+        // Every client needs to know when the tallies they display change
+        Ok(packet @ (Packet::TalliesReset { .. } | Packet::SegmentMarked { .. })) => {
+            broadcast(packet)
+        }
+        Ok(packet) => {
+            EncodedPacket::new(&packet)
+                .emit(&socket, client_encoding(&socket))
+                .ok();
+        }
+        Err(e) => emit_error(&socket, e),
+    }
+}
+
+fn emit_error(socket: &SocketRef, e: anyhow::Error) {
+    log::error!("{e}");
+    let packet = Packet::Error { msg: e.to_string() };
+    EncodedPacket::new(&packet)
+        .emit(socket, client_encoding(socket))
+        .ok();
+}
+
+fn client_encoding(socket: &SocketRef) -> Encoding {
+    CLIENTS
+        .lock()
+        .ok()
+        .and_then(|clients| {
+            clients
+                .get(&socket.id)
+                .map(|client| client.subscription.encoding)
+        })
+        .unwrap_or_default()
+}
+
+// Serialized as raw bytes so Socket.IO sends it as a binary attachment
+struct Binary<'a>(&'a [u8]);
+
+impl Serialize for Binary<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+// Encodes a packet at most once per encoding however many clients receive it
+struct EncodedPacket<'a> {
+    packet: &'a Packet,
+    json: Option<serde_json::Value>,
+    message_pack: Option<Vec<u8>>,
+}
+
+impl<'a> EncodedPacket<'a> {
+    fn new(packet: &'a Packet) -> Self {
+        Self {
+            packet,
+            json: None,
+            message_pack: None,
+        }
+    }
+
+    fn emit(&mut self, socket: &SocketRef, encoding: Encoding) -> Result<()> {
+        let name = self.packet.name();
+        match encoding {
+            Encoding::Json => {
+                let payload = self.json.get_or_insert_with(|| self.packet.payload());
+                socket.emit(name, &*payload)?;
+            }
+            Encoding::MessagePack => {
+                if self.message_pack.is_none() {
+                    self.message_pack = Some(self.packet.payload_message_pack()?);
+                }
+                let payload = self.message_pack.as_deref().unwrap_or_default();
+                socket.emit(name, &Binary(payload))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn handle_command(command: Command) -> Result<Packet> {
+    match command {
+        Command::ResetTallies => {
+            BattleContext::get_instance().reset_tallies();
+            Ok(Packet::TalliesReset {})
+        }
+        Command::MarkSegment { label } => {
+            let segment = BattleContext::get_instance().mark_segment(label);
+            Ok(Packet::SegmentMarked { segment })
+        }
+        Command::QueryHistory => {
+            let battle_context = BattleContext::get_instance();
+            Ok(Packet::History {
+                avatars: battle_context.avatar_lineup.clone(),
+                turn_history: battle_context.turn_history.clone(),
+                av_history: battle_context.av_history.clone(),
+                segment_markers: battle_context.segment_markers.clone(),
+                turn_count: battle_context.turn_count,
+                total_damage: battle_context.total_damage,
+                action_value: battle_context.action_value,
+            })
+        }
+        Command::Export { .. } | Command::ToggleWindow { .. } => {
+            let (responder, response) = oneshot::channel();
+            OVERLAY_REQUESTS
+                .lock()
+                .map_err(|e| anyhow!("{e}"))?
+                .push(OverlayRequest { command, responder });
+
+            tokio::time::timeout(OVERLAY_RESPONSE_TIMEOUT, response)
                 .await
-                .unwrap_or_else(|e| {
-                    log::error!("{e}");
-                    panic!("{e}");
-                });
+                .map_err(|_| anyhow!("Overlay did not respond"))?
+                .map_err(|_| anyhow!("Overlay dropped the request"))?
         }
-    });
+    }
+}
+
+#[derive(Default)]
+struct BroadcastQueue {
+    // None where a stat change was superseded by a newer one
+    packets: VecDeque<Option<Packet>>,
+    // Sequence number of the front of the queue, every push takes the next one
+    front_seq: u64,
+    // Sequence number of the stat change waiting to go out, by uid, team and stat kind
+    pending_stats: HashMap<(u32, Team, &'static str), u64>,
+    dropped: u64,
+}
+
+// Entity equality ignores the team, but avatars and enemies can share uids
+fn stat_key(packet: &Packet) -> Option<(u32, Team, &'static str)> {
+    match packet {
+        Packet::OnStatChange { entity, stat } => {
+            Some((entity.uid, entity.team.clone(), stat.kind()))
+        }
+        _ => None,
+    }
+}
+
+impl BroadcastQueue {
+    fn push(&mut self, packet: Packet) {
+        // Only the latest value of a stat matters, so the one still waiting to go out is
+        // replaced and the new value queued behind the packets that came before it
+        if let Some(key) = stat_key(&packet) {
+            let seq = self.front_seq + self.packets.len() as u64;
+            let pending = self.pending_stats.insert(key, seq).and_then(|pending_seq| {
+                self.packets
+                    .get_mut((pending_seq - self.front_seq) as usize)
+            });
+            if let Some(pending) = pending {
+                *pending = None;
+            }
+        }
+
+        if self.packets.len() >= BROADCAST_QUEUE_CAPACITY {
+            self.front_seq += 1;
+            // Superseded stat changes make room without dropping anything, and a stat
+            // change still in the queue is always the pending one for its stat
+            if let Some(Some(oldest)) = self.packets.pop_front() {
+                if let Some(key) = stat_key(&oldest) {
+                    self.pending_stats.remove(&key);
+                }
+                self.dropped += 1;
+                if self.dropped == 1 || self.dropped % 1000 == 0 {
+                    log::warn!("Broadcast queue is full, {} packets dropped", self.dropped);
+                }
+            }
+        }
+        self.packets.push_back(Some(packet));
+    }
+
+    fn len(&self) -> usize {
+        self.packets.iter().flatten().count()
+    }
+
+    fn take(&mut self) -> Vec<Packet> {
+        self.front_seq += self.packets.len() as u64;
+        self.pending_stats.clear();
+        self.packets.drain(..).flatten().collect()
+    }
+}
+
+static BROADCAST_QUEUE: LazyLock<Mutex<BroadcastQueue>> =
+    LazyLock::new(|| Mutex::new(BroadcastQueue::default()));
+
+static BROADCAST_NOTIFY: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+// Called from game hooks, so this only queues the packet and never blocks on clients
+pub fn broadcast(packet: Packet) {
+    match BROADCAST_QUEUE.lock() {
+        Ok(mut queue) => queue.push(packet),
+        Err(e) => {
+            log::error!("{e}");
+            return;
+        }
+    }
+    BROADCAST_NOTIFY.notify_one();
+}
+
+// Single consumer, so clients see packets in the order they were produced
+async fn dispatch_broadcasts() {
+    loop {
+        BROADCAST_NOTIFY.notified().await;
+        let packets = BROADCAST_QUEUE
+            .lock()
+            .map(|mut queue| queue.take())
+            .unwrap_or_default();
+        for packet in packets {
+            emit_to_clients(&packet);
+        }
+    }
+}
+
+fn emit_to_clients(packet: &Packet) {
+    let mut encoded = EncodedPacket::new(packet);
+    let Ok(mut clients) = CLIENTS.lock() else {
+        return;
+    };
+    for (sid, client) in clients.iter_mut() {
+        if !client.subscription.accepts(packet) {
+            continue;
+        }
+        // Fails instead of waiting when the client's buffer is full
+        if let Err(e) = encoded.emit(&client.socket, client.subscription.encoding) {
+            client.dropped += 1;
+            if client.dropped == 1 || client.dropped % 100 == 0 {
+                log::warn!(
+                    "Client {} has missed {} packets: {e}",
+                    sid,
+                    client.dropped
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{Body, to_bytes},
+        http::{HeaderValue, Request, StatusCode},
+    };
+    use socketioxide::SocketIo;
+    use tokio::sync::Mutex;
+    use tower::ServiceExt;
+
+    use super::{BROADCAST_QUEUE_CAPACITY, BroadcastQueue, is_local_origin, router};
+    use crate::{
+        battle::BattleContext,
+        models::{
+            commands::{Command, OverlayWindow, Subscription},
+            events::*,
+            misc::{Avatar, Entity, LineupChangeKind, Skill, Stat, Team},
+            packets::{Encoding, Packet},
+        },
+    };
+
+    async fn get(uri: &str) -> (StatusCode, String) {
+        let (layer, _io) = SocketIo::new_layer();
+        let response = router(layer)
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn serves_dashboard() {
+        let (status, body) = get("/").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("Damage Share"));
+        assert!(body.contains("/api/snapshot"));
+    }
+
+    // Tests driving the battle share its global context
+    static BATTLE: Mutex<()> = Mutex::const_new(());
+
+    #[tokio::test]
+    async fn snapshot_tracks_battle() {
+        let _battle = BATTLE.lock().await;
+        let avatars = vec![
+            Avatar { id: 1001, name: "March 7th".to_string(), build: None },
+            Avatar { id: 1002, name: "Dan Heng".to_string(), build: None },
+        ];
+        let attacker = Entity { uid: 1002, team: Team::Player };
+        let dan_heng = avatars[1].clone();
+
+        BattleContext::handle_event(Ok(Event::OnSetBattleLineup(OnSetLineupEvent { avatars })));
+        BattleContext::handle_event(Ok(Event::OnTurnBegin(OnTurnBeginEvent {
+            action_value: 50.,
+            turn_owner: Some(attacker.clone()),
+        })));
+        BattleContext::handle_event(Ok(Event::OnDamage(OnDamageEvent {
+            attacker,
+            avatar: dan_heng,
+            defender: None,
+            summon: None,
+            element: None,
+            damage: 1200.,
+            damage_type: 2,
+            overkill_damage: 0.,
+        })));
+        BattleContext::handle_event(Ok(Event::OnTurnEnd));
+
+        let (status, body) = get("/api/snapshot").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let snapshot: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(snapshot["avatars"][1]["name"], "Dan Heng");
+        assert_eq!(snapshot["damages"], serde_json::json!([0.0, 1200.0]));
+        assert_eq!(snapshot["total_damage"], 1200.0);
+        assert_eq!(snapshot["action_value"], 50.0);
+        assert_eq!(snapshot["turn_history"][0]["avatars_turn_damage"][1], 1200.0);
+    }
+
+    #[tokio::test]
+    async fn reset_starts_every_history_over() {
+        let _battle = BATTLE.lock().await;
+        let dan_heng = Avatar { id: 1002, name: "Dan Heng".to_string(), build: None };
+        let attacker = Entity { uid: 1002, team: Team::Player };
+        let enemy = Entity { uid: 7, team: Team::Enemy };
+
+        BattleContext::handle_event(Ok(Event::OnSetBattleLineup(OnSetLineupEvent {
+            avatars: vec![dan_heng.clone()],
+        })));
+        BattleContext::handle_event(Ok(Event::OnTurnBegin(OnTurnBeginEvent {
+            action_value: 80.,
+            turn_owner: Some(attacker.clone()),
+        })));
+        BattleContext::handle_event(Ok(Event::OnDamage(OnDamageEvent {
+            attacker: attacker.clone(),
+            avatar: dan_heng,
+            defender: Some(enemy.clone()),
+            summon: None,
+            element: None,
+            damage: 1200.,
+            damage_type: 1,
+            overkill_damage: 0.,
+        })));
+        BattleContext::handle_event(Ok(Event::OnEntityDefeated(OnEntityDefeatedEvent {
+            killer: attacker,
+            entity_defeated: enemy,
+        })));
+        BattleContext::handle_event(Ok(Event::OnTurnEnd));
+
+        let mut battle_context = BattleContext::get_instance();
+        battle_context.reset_tallies();
+        assert_eq!(battle_context.avatar_lineup.len(), 1);
+        assert_eq!(battle_context.action_value, 80.);
+        assert_eq!(battle_context.total_damage, 0.);
+        assert!(battle_context.turn_history.is_empty());
+        assert!(battle_context.entity_turn_history.is_empty());
+        assert!(battle_context.defeat_history.is_empty());
+        assert!(battle_context.hit_history.is_empty());
+        assert!(!battle_context.in_technique_phase());
+        // The current wave is tallied from the reset on
+        assert_eq!(battle_context.wave_segments.len(), 1);
+        assert_eq!(battle_context.wave_segments[0].start_action_value, 80.);
+    }
+
+    #[tokio::test]
+    async fn registers_avatars_joining_mid_battle() {
+        let _battle = BATTLE.lock().await;
+        let march = Avatar { id: 1001, name: "March 7th".to_string(), build: None };
+        let dan_heng = Avatar { id: 1002, name: "Dan Heng".to_string(), build: None };
+        // Trial character that wasn't in the lineup the battle was set up with
+        let himeko = Avatar { id: 1003, name: "Himeko".to_string(), build: None };
+        let formation = |avatars: &[&Avatar]| {
+            Event::OnUpdateTeamFormation(OnUpdateTeamFormationEvent {
+                entities: avatars
+                    .iter()
+                    .map(|avatar| Entity { uid: avatar.id, team: Team::Player })
+                    .collect(),
+                team: Team::Player,
+                avatars: avatars.iter().map(|avatar| (*avatar).clone()).collect(),
+            })
+        };
+
+        BattleContext::handle_event(Ok(Event::OnSetBattleLineup(OnSetLineupEvent {
+            avatars: vec![march.clone(), dan_heng.clone()],
+        })));
+        BattleContext::handle_event(Ok(formation(&[&march, &dan_heng])));
+        BattleContext::handle_event(Ok(Event::OnTurnBegin(OnTurnBeginEvent {
+            action_value: 50.,
+            turn_owner: Some(Entity { uid: 1003, team: Team::Player }),
+        })));
+        BattleContext::handle_event(Ok(Event::OnDamage(OnDamageEvent {
+            attacker: Entity { uid: 1003, team: Team::Player },
+            avatar: himeko.clone(),
+            defender: None,
+            summon: None,
+            element: None,
+            damage: 800.,
+            damage_type: 1,
+            overkill_damage: 0.,
+        })));
+        BattleContext::handle_event(Ok(Event::OnTurnEnd));
+        BattleContext::handle_event(Ok(formation(&[&march, &himeko])));
+
+        let (_, body) = get("/api/snapshot").await;
+        let snapshot: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(snapshot["avatars"][2]["name"], "Himeko");
+        assert_eq!(snapshot["damages"], serde_json::json!([0.0, 0.0, 800.0]));
+        assert_eq!(snapshot["turn_history"][0]["avatars_turn_damage"][2], 800.0);
+
+        let changes = BattleContext::get_instance()
+            .lineup_changes
+            .iter()
+            .map(|change| (change.avatar_id, change.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [(1003, LineupChangeKind::Joined), (1002, LineupChangeKind::Left)]
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_stats_per_team() {
+        let _battle = BATTLE.lock().await;
+        let avatar = Entity { uid: 1, team: Team::Player };
+        // Shares its uid with the avatar
+        let enemy = Entity { uid: 1, team: Team::Enemy };
+
+        BattleContext::handle_event(Ok(Event::OnSetBattleLineup(OnSetLineupEvent {
+            avatars: vec![Avatar { id: 1, name: "March 7th".to_string(), build: None }],
+        })));
+        BattleContext::handle_event(Ok(Event::OnStatChange(OnStatChangeEvent {
+            entity: avatar.clone(),
+            stat: Stat::Defense(1200.),
+        })));
+        BattleContext::handle_event(Ok(Event::OnStatChange(OnStatChangeEvent {
+            entity: enemy.clone(),
+            stat: Stat::Defense(800.),
+        })));
+
+        BattleContext::handle_event(Ok(Event::OnDamage(OnDamageEvent {
+            attacker: avatar.clone(),
+            avatar: Avatar { id: 1, name: "March 7th".to_string(), build: None },
+            defender: Some(enemy.clone()),
+            summon: None,
+            element: None,
+            damage: 500.,
+            damage_type: 1,
+            overkill_damage: 0.,
+        })));
+
+        let battle_context = BattleContext::get_instance();
+        let defense = |entity| battle_context.stats_of(entity).unwrap()["Defense"];
+        assert_eq!(defense(&avatar), 1200.);
+        assert_eq!(defense(&enemy), 800.);
+        // The hit is checked against the defender's DEF rather than the attacker's
+        assert_eq!(battle_context.hit_history[0].defender_stats.defense, 800.);
+    }
+
+    #[tokio::test]
+    async fn matches_hits_to_skills_of_their_attack_type() {
+        let _battle = BATTLE.lock().await;
+        let march = Avatar { id: 1001, name: "March 7th".to_string(), build: None };
+        let attacker = Entity { uid: 1001, team: Team::Player };
+        let hit = |damage_type| {
+            Event::OnDamage(OnDamageEvent {
+                attacker: attacker.clone(),
+                avatar: march.clone(),
+                defender: Some(Entity { uid: 7, team: Team::Enemy }),
+                summon: None,
+                element: None,
+                damage: 300.,
+                damage_type,
+                overkill_damage: 0.,
+            })
+        };
+
+        BattleContext::handle_event(Ok(Event::OnSetBattleLineup(OnSetLineupEvent {
+            avatars: vec![march.clone()],
+        })));
+        BattleContext::handle_event(Ok(Event::OnTurnBegin(OnTurnBeginEvent {
+            action_value: 0.,
+            turn_owner: Some(attacker.clone()),
+        })));
+        BattleContext::handle_event(Ok(Event::OnUseSkill(OnUseSkillEvent {
+            avatar: attacker.clone(),
+            skill: Skill {
+                name: "Frigid Cold Arrow".to_string(),
+                skill_type: 1,
+                skill_config_id: 0,
+            },
+            insert: false,
+        })));
+        BattleContext::handle_event(Ok(hit(1)));
+        // DoT tick in the same turn
+        BattleContext::handle_event(Ok(hit(5)));
+        BattleContext::handle_event(Ok(Event::OnTurnEnd));
+        BattleContext::handle_event(Ok(Event::OnTurnBegin(OnTurnBeginEvent {
+            action_value: 50.,
+            turn_owner: Some(Entity { uid: 7, team: Team::Enemy }),
+        })));
+        // Counter during an enemy turn without a skill of its own
+        BattleContext::handle_event(Ok(hit(1)));
+
+        let battle_context = BattleContext::get_instance();
+        let skill_names = battle_context
+            .hit_history
+            .iter()
+            .map(|hit| hit.skill_name.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(skill_names, [Some("Frigid Cold Arrow"), None, None]);
+    }
+
+    #[test]
+    fn parses_commands() {
+        let command: Command =
+            serde_json::from_str(r#"{"type": "ToggleWindow", "window": "DamageBars"}"#).unwrap();
+        assert!(matches!(
+            command,
+            Command::ToggleWindow {
+                window: OverlayWindow::DamageBars,
+                visible: None
+            }
+        ));
+
+        let command: Command =
+            serde_json::from_str(r#"{"type": "MarkSegment", "label": "Wave 2"}"#).unwrap();
+        assert!(matches!(command, Command::MarkSegment { label } if label == "Wave 2"));
+
+        assert!(serde_json::from_str::<Command>(r#"{"type": "Explode"}"#).is_err());
+    }
+
+    #[test]
+    fn allows_local_origins() {
+        let is_local = |origin| is_local_origin(&HeaderValue::from_static(origin));
+        assert!(is_local("http://localhost"));
+        assert!(is_local("http://127.0.0.1:1305"));
+        assert!(is_local("http://[::1]:5173"));
+        assert!(!is_local("https://example.com"));
+        assert!(!is_local("http://localhost.example.com"));
+        assert!(!is_local("null"));
+    }
+
+    #[test]
+    fn coalesces_stat_changes() {
+        let stat_change = |uid, team, stat| Packet::OnStatChange {
+            entity: Entity { uid, team },
+            stat,
+        };
+
+        let mut queue = BroadcastQueue::default();
+        queue.push(stat_change(1, Team::Enemy, Stat::HP(100.)));
+        queue.push(stat_change(1, Team::Enemy, Stat::Speed(90.)));
+        queue.push(stat_change(2, Team::Enemy, Stat::HP(500.)));
+        // Shares its uid with the first enemy
+        queue.push(stat_change(1, Team::Player, Stat::HP(3000.)));
+        queue.push(stat_change(1, Team::Enemy, Stat::HP(40.)));
+
+        let packets = queue.take();
+        assert_eq!(packets.len(), 4);
+        assert!(matches!(
+            packets[2],
+            Packet::OnStatChange { entity: Entity { team: Team::Player, .. }, stat: Stat::HP(hp) } if hp == 3000.
+        ));
+        assert!(matches!(
+            packets[3],
+            Packet::OnStatChange { stat: Stat::HP(hp), .. } if hp == 40.
+        ));
+    }
+
+    #[test]
+    fn drops_oldest_when_full() {
+        let mut queue = BroadcastQueue::default();
+        for wave in 0..=BROADCAST_QUEUE_CAPACITY as u32 {
+            queue.push(Packet::OnUpdateWave { wave });
+        }
+
+        assert_eq!(queue.len(), BROADCAST_QUEUE_CAPACITY);
+        assert_eq!(queue.dropped, 1);
+        assert!(matches!(queue.take()[0], Packet::OnUpdateWave { wave: 1 }));
+    }
+
+    #[test]
+    fn filters_by_subscription() {
+        let subscription: Subscription = serde_json::from_str(
+            r#"{"events": ["OnStatChange", "OnDamage"], "stats": ["HP"], "entities": [{"uid": 7, "team": "Enemy"}]}"#,
+        )
+        .unwrap();
+        assert!(subscription.validate().is_ok());
+
+        let enemy = |uid| Entity { uid, team: Team::Enemy };
+        assert!(subscription.accepts(&Packet::OnStatChange {
+            entity: enemy(7),
+            stat: Stat::HP(10.)
+        }));
+        assert!(!subscription.accepts(&Packet::OnStatChange {
+            entity: enemy(7),
+            stat: Stat::Speed(10.)
+        }));
+        assert!(!subscription.accepts(&Packet::OnStatChange {
+            entity: enemy(8),
+            stat: Stat::HP(10.)
+        }));
+        // An avatar sharing the enemy's uid
+        assert!(!subscription.accepts(&Packet::OnStatChange {
+            entity: Entity { uid: 7, team: Team::Player },
+            stat: Stat::HP(10.)
+        }));
+        assert!(subscription.accepts(&Packet::OnDamage {
+            attacker: Entity { uid: 1001, team: Team::Player },
+            defender: Some(enemy(7)),
+            summon: None,
+            damage: 100.,
+            damage_type: 1,
+        }));
+        assert!(!subscription.accepts(&Packet::OnUpdateWave { wave: 1 }));
+
+        assert!(Subscription::default().accepts(&Packet::OnUpdateWave { wave: 1 }));
+        assert!(
+            serde_json::from_str::<Subscription>(r#"{"stats": ["Luck"]}"#)
+                .unwrap()
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn encodes_message_pack() {
+        let subscription: Subscription =
+            serde_json::from_str(r#"{"encoding": "msgpack"}"#).unwrap();
+        assert_eq!(subscription.encoding, Encoding::MessagePack);
+
+        let packet = Packet::OnTurnBegin {
+            action_value: 12.5,
+            turn_owner: Some(Entity { uid: 1310, team: Team::Player }),
+        };
+        let decoded: serde_json::Value =
+            rmp_serde::from_slice(&packet.payload_message_pack().unwrap()).unwrap();
+        assert_eq!(decoded, packet.payload());
+    }
 }
@@ -5,7 +5,10 @@ use crate::kreide::*;
 
 use crate::models::events::*;
 use crate::models::misc::Avatar;
+use crate::models::misc::Element;
+use crate::models::misc::ElementResistance;
 use crate::models::misc::Enemy;
+use crate::models::misc::EnemyRank;
 use crate::models::misc::Entity;
 use crate::models::misc::Stat;
 use crate::models::misc::Stats;
@@ -80,6 +83,13 @@ fn on_damage(
                     0.0
                 };
                 let damage_type = kcekdanaofi.IDFIOBDMLFF()?;
+                let defender_entity = match defender._EntityType()? {
+                    RPG_GameCore_EntityType::Monster => Some(Entity {
+                        uid: defender._RuntimeID_k__BackingField()?,
+                        team: Team::Enemy,
+                    }),
+                    _ => None,
+                };
                 let attack_owner = {
                     let attack_owner = RPG_GameCore_AbilityStatic::get_actual_owner(attacker)?;
                     if !attack_owner.is_null() {
@@ -97,6 +107,10 @@ fn on_damage(
                                     uid: avatar.id,
                                     team: Team::Player,
                                 },
+                                element: helpers::get_avatar_element(avatar.id).ok(),
+                                avatar,
+                                defender: defender_entity.clone(),
+                                summon: None,
                                 damage,
                                 damage_type: damage_type as isize,
                                 overkill_damage,
@@ -115,6 +129,10 @@ fn on_damage(
                                     uid: avatar.id,
                                     team: Team::Player,
                                 },
+                                element: helpers::get_avatar_element(avatar.id).ok(),
+                                avatar,
+                                defender: defender_entity.clone(),
+                                summon: helpers::get_servant_from_entity(attack_owner).ok(),
                                 damage,
                                 damage_type: damage_type as isize,
                                 overkill_damage,
@@ -130,12 +148,33 @@ fn on_damage(
                         // Unsure if this is if only a servant died and inflicted a DOT
                         let character_data_comp = attacker_ability._CharacterDataRef()?;
                         let summoner_entity = character_data_comp.Summoner()?;
-                        let e = match helpers::get_avatar_from_entity(summoner_entity) {
+                        // Snapshots left behind by servants are summoned by the servant itself,
+                        // otherwise the snapshot carries the servant data of what it stands in for
+                        let summoned_by_servant = matches!(
+                            summoner_entity._EntityType()?,
+                            RPG_GameCore_EntityType::Servant
+                        );
+                        let (avatar, servant_entity) = if summoned_by_servant {
+                            (
+                                helpers::get_avatar_from_servant_entity(summoner_entity),
+                                summoner_entity,
+                            )
+                        } else {
+                            (
+                                helpers::get_avatar_from_entity(summoner_entity),
+                                attack_owner,
+                            )
+                        };
+                        let e = match avatar {
                             Ok(avatar) => Ok(Event::OnDamage(OnDamageEvent {
                                 attacker: Entity {
                                     uid: avatar.id,
                                     team: Team::Player,
                                 },
+                                element: helpers::get_avatar_element(avatar.id).ok(),
+                                avatar,
+                                defender: defender_entity.clone(),
+                                summon: helpers::get_servant_from_entity(servant_entity).ok(),
                                 damage,
                                 damage_type: damage_type as isize,
                                 overkill_damage,
@@ -217,6 +256,7 @@ fn on_use_skill(
                                                 team: Team::Player,
                                             },
                                             skill,
+                                            insert: false,
                                         }))
                                     }
                                     Err(e) => {
@@ -249,6 +289,7 @@ fn on_use_skill(
                                             team: Team::Player,
                                         },
                                         skill,
+                                        insert: false,
                                     })),
                                     Err(e) => {
                                         log::error!("Servant Event Error: {}", e);
@@ -286,6 +327,7 @@ fn on_use_skill(
                                             team: Team::Player,
                                         },
                                         skill,
+                                        insert: false,
                                     })),
                                     Err(e) => {
                                         log::error!("Summon Event Error: {}", e);
@@ -373,6 +415,7 @@ fn on_combo(instance: MAJMDHFBJAB, game_mode: RPG_GameCore_TurnBasedGameMode) {
                                                     team: Team::Player,
                                                 },
                                                 skill,
+                                                insert: true,
                                             }))
                                         }
                                         Err(e) => {
@@ -404,6 +447,7 @@ fn on_combo(instance: MAJMDHFBJAB, game_mode: RPG_GameCore_TurnBasedGameMode) {
                                             team: Team::Player,
                                         },
                                         skill,
+                                        insert: true,
                                     })),
                                     Err(e) => {
                                         log::error!("Servant Event Error: {}", e);
@@ -441,6 +485,7 @@ fn on_combo(instance: MAJMDHFBJAB, game_mode: RPG_GameCore_TurnBasedGameMode) {
                                             team: Team::Player,
                                         },
                                         skill,
+                                        insert: true,
                                     })),
                                     Err(e) => {
                                         log::error!("Summon Event Error: {}", e);
@@ -478,6 +523,15 @@ fn on_combo(instance: MAJMDHFBJAB, game_mode: RPG_GameCore_TurnBasedGameMode) {
     });
 }
 
+// The build is optional so a layout change on the game side doesn't drop the lineup
+fn with_build(mut avatar: Avatar, character: RPG_GameCore_LineUpCharacter) -> Avatar {
+    match unsafe { helpers::get_avatar_build(character) } {
+        Ok(build) => avatar.build = Some(build),
+        Err(e) => log::error!("Avatar {} build error: {}", avatar.id, e),
+    }
+    avatar
+}
+
 #[named]
 fn on_set_lineup(instance: RPG_GameCore_BattleInstance, a1: *const c_void, a2: RPG_GameCore_BattleLineupData, a3: i32, a4: u32, a5: bool) {
     log::debug!(function_name!());
@@ -488,7 +542,7 @@ fn on_set_lineup(instance: RPG_GameCore_BattleInstance, a1: *const c_void, a2: R
         for character in light_team.to_vec::<RPG_GameCore_LineUpCharacter>() {
             let avatar_id = character.CharacterID()?;
             match helpers::get_avatar_from_id(avatar_id) {
-                Ok(avatar) => avatars.push(avatar),
+                Ok(avatar) => avatars.push(with_build(avatar, character)),
                 Err(e) => errors.push(e)
             }
         }
@@ -498,7 +552,7 @@ fn on_set_lineup(instance: RPG_GameCore_BattleInstance, a1: *const c_void, a2: R
         for character in extra_team.to_vec::<RPG_GameCore_LineUpCharacter>() {
             let avatar_id = character.CharacterID()?;
             match helpers::get_avatar_from_id(avatar_id) {
-                Ok(avatar) => avatars.push(avatar),
+                Ok(avatar) => avatars.push(with_build(avatar, character)),
                 Err(e) => errors.push(e)
             }
         }
@@ -1338,7 +1392,28 @@ pub fn on_entity_defeated(instance: RPG_GameCore_TurnBasedGameMode, a2: DKKNECHP
                     }
                 };
                 BattleContext::handle_event(e);
-            };
+            } else if killer_entity._EntityType()? == RPG_GameCore_EntityType::Monster
+                && defeated_entity._EntityType()? == RPG_GameCore_EntityType::Avatar
+            {
+                let e = match helpers::get_avatar_from_entity(defeated_entity) {
+                    Ok(avatar) => Ok(Event::OnEntityDefeated(OnEntityDefeatedEvent {
+                        killer: Entity {
+                            uid: killer_entity._RuntimeID_k__BackingField()?,
+                            team: Team::Enemy,
+                        },
+                        entity_defeated: Entity {
+                            uid: avatar.id,
+                            team: Team::Player,
+                        },
+                    })),
+                    Err(e) => {
+                        log::error!("Avatar Event Error: {}", e);
+
+                        Err(anyhow!("{} Avatar Event Error: {}", function_name!(), e))
+                    }
+                };
+                BattleContext::handle_event(e);
+            }
         }
         Ok(())
     });
@@ -1350,33 +1425,79 @@ pub fn on_update_team_formation(instance: RPG_GameCore_TeamFormationComponent) {
     log::debug!(function_name!());
     let res = ON_UPDATE_TEAM_FORMATION_Detour.call(instance);
     safe_call!({
-        if instance._Team()? == RPG_GameCore_TeamType::TeamDark {
-            let team = instance._TeamFormationDatas()?;
-            let entities = team
-                .to_vec::<EDJEDBLFIKE>()
-                .iter()
-                .map(|entity_formation| Entity {
-                    uid: entity_formation
-                        ._OwnerRef()
-                        .unwrap()
-                        ._RuntimeID_k__BackingField()
-                        .unwrap(),
-                    team: Team::Enemy,
-                })
-                .collect::<Vec<Entity>>();
+        match instance._Team()? {
+            RPG_GameCore_TeamType::TeamDark => {
+                let team = instance._TeamFormationDatas()?;
+                let entities = team
+                    .to_vec::<EDJEDBLFIKE>()
+                    .iter()
+                    .map(|entity_formation| Entity {
+                        uid: entity_formation
+                            ._OwnerRef()
+                            .unwrap()
+                            ._RuntimeID_k__BackingField()
+                            .unwrap(),
+                        team: Team::Enemy,
+                    })
+                    .collect::<Vec<Entity>>();
 
-            BattleContext::handle_event(Ok(Event::OnUpdateTeamFormation(
-                OnUpdateTeamFormationEvent {
-                    entities,
-                    team: Team::Enemy,
-                },
-            )));
+                BattleContext::handle_event(Ok(Event::OnUpdateTeamFormation(
+                    OnUpdateTeamFormationEvent {
+                        entities,
+                        team: Team::Enemy,
+                        avatars: Vec::new(),
+                    },
+                )));
+            }
+            RPG_GameCore_TeamType::TeamLight => {
+                let team = instance._TeamFormationDatas()?;
+                // Servants share the formation with avatars but aren't part of the lineup
+                let avatars = team
+                    .to_vec::<EDJEDBLFIKE>()
+                    .iter()
+                    .filter_map(|entity_formation| entity_formation._OwnerRef().ok())
+                    .filter(|owner| {
+                        owner
+                            ._EntityType()
+                            .is_ok_and(|entity_type| entity_type == RPG_GameCore_EntityType::Avatar)
+                    })
+                    .filter_map(|owner| unsafe { helpers::get_avatar_from_entity(owner) }.ok())
+                    .collect::<Vec<Avatar>>();
+                let entities = avatars
+                    .iter()
+                    .map(|avatar| Entity {
+                        uid: avatar.id,
+                        team: Team::Player,
+                    })
+                    .collect::<Vec<Entity>>();
+
+                BattleContext::handle_event(Ok(Event::OnUpdateTeamFormation(
+                    OnUpdateTeamFormationEvent {
+                        entities,
+                        team: Team::Player,
+                        avatars,
+                    },
+                )));
+            }
+            _ => {}
         }
         Ok(())
     });
     res
 }
 
+fn resistance_property(element: Element) -> RPG_GameCore_AbilityProperty {
+    match element {
+        Element::Physical => RPG_GameCore_AbilityProperty::PhysicalResistance,
+        Element::Fire => RPG_GameCore_AbilityProperty::FireResistance,
+        Element::Ice => RPG_GameCore_AbilityProperty::IceResistance,
+        Element::Lightning => RPG_GameCore_AbilityProperty::ThunderResistance,
+        Element::Wind => RPG_GameCore_AbilityProperty::WindResistance,
+        Element::Quantum => RPG_GameCore_AbilityProperty::QuantumResistance,
+        Element::Imaginary => RPG_GameCore_AbilityProperty::ImaginaryResistance,
+    }
+}
+
 #[named]
 pub fn on_initialize_enemy(
     instance: RPG_GameCore_MonsterDataComponent,
@@ -1397,12 +1518,56 @@ pub fn on_initialize_enemy(
         let monster_name = get_textmap_content(&name_id)?;
         let entity = instance._OwnerRef()?;
 
+        // Metadata is best effort so a missing field doesn't drop the enemy
+        let weaknesses = row
+            .StanceWeakList()
+            .map(|weak_list| {
+                if weak_list.0 == 0 {
+                    return Vec::new();
+                }
+                weak_list
+                    .to_vec::<i32>()
+                    .into_iter()
+                    .filter_map(Element::from_damage_type)
+                    .collect()
+            })
+            .unwrap_or_else(|e| {
+                log::error!("{} weaknesses error: {}", function_name!(), e);
+                Vec::new()
+            });
+        let resistances = Element::ALL
+            .into_iter()
+            .filter_map(|element| {
+                turn_based_ability_component
+                    .get_property(resistance_property(element))
+                    .ok()
+                    .map(|value| ElementResistance {
+                        element,
+                        value: fixpoint_to_raw(&value),
+                    })
+            })
+            .collect();
+        let max_toughness = turn_based_ability_component
+            .get_property(RPG_GameCore_AbilityProperty::MaxStance)
+            .map(|value| fixpoint_to_raw(&value))
+            .unwrap_or_default();
+
         BattleContext::handle_event(Ok(Event::OnInitializeEnemy(OnInitializeEnemyEvent {
             enemy: Enemy {
                 id: monster_id,
                 uid: entity._RuntimeID_k__BackingField()?,
                 name: (*monster_name).to_string(),
                 base_stats,
+                weaknesses,
+                resistances,
+                max_toughness,
+                rank: row_data
+                    .get_rank()
+                    .map(EnemyRank::from_monster_rank)
+                    .unwrap_or_default(),
+                elite_group: row.EliteGroup().unwrap_or_default(),
+                wave: 0,
+                position: 0,
             },
         })));
         Ok(())
@@ -33,6 +33,9 @@ use crate::RUNTIME;
 use crate::battle::BattleContext;
 use crate::entry::InitErrorInfo;
 use crate::export::BattleDataExporter;
+use crate::models::commands::{Command, OverlayWindow};
+use crate::models::packets::Packet;
+use crate::server;
 use crate::updater::Status;
 use crate::updater::Update;
 use crate::updater::Updater;
@@ -44,6 +47,8 @@ pub enum GraphUnit {
     #[default]
     Turn,
     ActionValue,
+    // Enemy HP over AV instead of damage
+    EnemyHp,
 }
 
 #[derive(Clone)]
@@ -64,8 +69,21 @@ pub struct AppState {
     pub show_real_time_damage: bool,
     pub show_enemy_stats: bool,
     pub show_battle_metrics: bool,
+    #[serde(default)]
+    pub show_mode_metrics: bool,
+    #[serde(default)]
+    pub show_action_timeline: bool,
+    #[serde(default)]
+    pub show_speed_analysis: bool,
+    #[serde(default)]
+    pub show_rotation_analysis: bool,
+    #[serde(default)]
+    pub show_enabled_damage_distribution: bool,
     pub should_hide: bool,
     pub graph_x_unit: GraphUnit,
+    // Wave shown by the real-time graph, all waves when None
+    #[serde(skip)]
+    pub graph_wave: Option<u32>,
     #[serde(skip)]
     pub use_custom_color: bool,
     #[serde(skip)]
@@ -189,6 +207,26 @@ impl Overlay for App {
             if self.state.show_enemy_stats {
                 self.show_enemy_stats_window(ctx);
             }
+
+            if self.state.show_mode_metrics {
+                self.show_mode_metrics_window(ctx);
+            }
+
+            if self.state.show_action_timeline {
+                self.show_action_timeline_window(ctx);
+            }
+
+            if self.state.show_speed_analysis {
+                self.show_speed_analysis_window(ctx);
+            }
+
+            if self.state.show_rotation_analysis {
+                self.show_rotation_analysis_window(ctx);
+            }
+
+            if self.state.show_enabled_damage_distribution {
+                self.show_enabled_damage_distribution_window(ctx);
+            }
         }
 
         // This is a weird quirk of immediate mode where we must initialize our state a frame later
@@ -260,6 +298,11 @@ impl Overlay for App {
             //     self.state.update_available.as_ref().unwrap());
         }
 
+        for request in server::take_overlay_requests() {
+            let result = self.handle_overlay_command(&request.command);
+            request.respond(result);
+        }
+
         if let Some(state) = BattleContext::get_instance().state.take() {
             match state {
                 crate::battle::BattleState::Started => {
@@ -420,8 +463,14 @@ impl Default for AppState {
             show_real_time_damage: false,
             show_enemy_stats: false,
             show_battle_metrics: false,
+            show_mode_metrics: false,
+            show_action_timeline: false,
+            show_speed_analysis: false,
+            show_rotation_analysis: false,
+            show_enabled_damage_distribution: false,
             should_hide: false,
             graph_x_unit: GraphUnit::default(),
+            graph_wave: None,
             use_custom_color: false,
             update_bttn_enabled: false,
             show_version_mismatch: false,
@@ -437,6 +486,27 @@ impl Default for AppState {
 }
 
 impl AppState {
+    pub fn window_visibility_mut(&mut self, window: OverlayWindow) -> &mut bool {
+        match window {
+            OverlayWindow::Menu => &mut self.show_menu,
+            OverlayWindow::Console => &mut self.show_console,
+            OverlayWindow::DamageDistribution => &mut self.show_damage_distribution,
+            OverlayWindow::CharacterLegend => &mut self.show_character_legend,
+            OverlayWindow::DamageBars => &mut self.show_damage_bars,
+            OverlayWindow::RealTimeDamage => &mut self.show_real_time_damage,
+            OverlayWindow::BattleMetrics => &mut self.show_battle_metrics,
+            OverlayWindow::EnemyStats => &mut self.show_enemy_stats,
+            OverlayWindow::ModeMetrics => &mut self.show_mode_metrics,
+            OverlayWindow::ActionTimeline => &mut self.show_action_timeline,
+            OverlayWindow::SpeedAnalysis => &mut self.show_speed_analysis,
+            OverlayWindow::RotationAnalysis => &mut self.show_rotation_analysis,
+            OverlayWindow::EnabledDamageDistribution => {
+                &mut self.show_enabled_damage_distribution
+            }
+            OverlayWindow::Export => &mut self.show_export_window,
+        }
+    }
+
     fn load() -> Result<Self> {
         match ProjectDirs::from("", "", env!("CARGO_PKG_NAME")) {
             Some(proj_dirs) => {
@@ -688,9 +758,16 @@ impl App {
     }
 
     pub fn export_battle_data(&self, format: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.export_battle_data_to(format, self.state.custom_export_path.as_deref())
+    }
+
+    pub fn export_battle_data_to(
+        &self,
+        format: &str,
+        custom_path: Option<&str>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
         let battle_context = BattleContext::get_instance();
         let exporter = BattleDataExporter::new();
-        let custom_path = self.state.custom_export_path.as_deref();
 
         match format {
             "json" => exporter.export_to_file_with_custom_path(
@@ -709,6 +786,31 @@ impl App {
         }
     }
 
+    // Commands sent by socket clients that need the overlay's state
+    fn handle_overlay_command(&mut self, command: &Command) -> Result<Packet> {
+        match command {
+            Command::Export { format } => {
+                let path = self
+                    .export_battle_data(format.extension())
+                    .map_err(|e| anyhow!("Failed to export {}: {e}", format.extension()))?;
+                log::info!("Exported battle data to: {}", path);
+                Ok(Packet::Exported {
+                    format: *format,
+                    path,
+                })
+            }
+            Command::ToggleWindow { window, visible } => {
+                let shown = self.state.window_visibility_mut(*window);
+                *shown = visible.unwrap_or(!*shown);
+                Ok(Packet::WindowToggled {
+                    window: *window,
+                    visible: *shown,
+                })
+            }
+            _ => Err(anyhow!("{:?} is not an overlay command", command)),
+        }
+    }
+
     pub fn open_folder(&mut self, path: &str) {
         #[cfg(target_os = "windows")]
         {
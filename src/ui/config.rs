@@ -32,6 +32,8 @@ pub struct Config {
     pub legend_text_style: egui::TextStyle,
     #[serde(default = "default_pie_chart_opacity")]
     pub pie_chart_opacity: f32,
+    #[serde(default = "default_fold_summon_damage")]
+    pub fold_summon_damage: bool,
     #[serde(default = "default_defender_exclusion")]
     pub defender_exclusion: bool,
     #[serde(default = "default_auto_showhide_ui")]
@@ -77,6 +79,10 @@ fn default_pie_chart_opacity() -> f32 {
     0.05
 }
 
+fn default_fold_summon_damage() -> bool {
+    false
+}
+
 fn default_defender_exclusion() -> bool {
     true
 }
@@ -103,6 +109,7 @@ impl Default for Config {
             font_sizes: default_font_sizes(),
             legend_text_style: default_legend_text_style(),
             pie_chart_opacity: default_pie_chart_opacity(),
+            fold_summon_damage: default_fold_summon_damage(),
             defender_exclusion: default_defender_exclusion(),
             auto_showhide_ui: default_auto_showhide_ui(),
             nag_versions: default_nag_versions(),
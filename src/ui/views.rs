@@ -363,6 +363,10 @@ impl App {
                                 &mut self.state.show_damage_distribution,
                                 t!("Show Damage Distribution"),
                             );
+                            ui.checkbox(
+                                &mut self.state.show_enabled_damage_distribution,
+                                t!("Show Enabled Damage Distribution"),
+                            );
                             ui.checkbox(
                                 &mut self.state.show_damage_bars,
                                 t!("Show Damage Bars"),
@@ -381,6 +385,26 @@ impl App {
                                 t!("Show Battle Metrics"),
                             );
 
+                            ui.checkbox(
+                                &mut self.state.show_mode_metrics,
+                                t!("Show Mode Metrics"),
+                            );
+
+                            ui.checkbox(
+                                &mut self.state.show_action_timeline,
+                                t!("Show Action Timeline"),
+                            );
+
+                            ui.checkbox(
+                                &mut self.state.show_speed_analysis,
+                                t!("Show Speed Analysis"),
+                            );
+
+                            ui.checkbox(
+                                &mut self.state.show_rotation_analysis,
+                                t!("Show Rotation Analysis"),
+                            );
+
                             ui.add_space(5.);
 
                             ui.separator();
@@ -725,6 +749,11 @@ impl App {
                 ui.label(t!("Pie Chart Opacity"));
             });
 
+            ui.checkbox(
+                &mut self.config.fold_summon_damage,
+                t!("Fold summon damage into their owner"),
+            );
+
             ui.add(
                 Slider::new(&mut self.config.widget_opacity, 0.0..=1.0).text(t!("Window Opacity")),
             );
@@ -903,4 +932,65 @@ impl App {
                 self.show_enemy_stats_widget(ui);
             });
     }
+
+    pub fn show_mode_metrics_window(&mut self, ctx: &egui::Context) {
+        egui::containers::Window::new(t!("Mode Metrics"))
+            .id("mode_metrics_window".into())
+            .frame(get_window_frame(ctx, self.config.widget_opacity))
+            .resizable(true)
+            .min_width(200.0)
+            .min_height(100.0)
+            .show(ctx, |ui| {
+                self.show_mode_metrics_widget(ui);
+            });
+    }
+
+    pub fn show_action_timeline_window(&mut self, ctx: &egui::Context) {
+        egui::containers::Window::new(t!("Action Timeline"))
+            .id("action_timeline_window".into())
+            .frame(get_window_frame(ctx, self.config.widget_opacity))
+            .resizable(true)
+            .min_width(300.0)
+            .min_height(200.0)
+            .show(ctx, |ui| {
+                self.show_action_timeline_widget(ui);
+            });
+    }
+
+    pub fn show_speed_analysis_window(&mut self, ctx: &egui::Context) {
+        egui::containers::Window::new(t!("Speed Analysis"))
+            .id("speed_analysis_window".into())
+            .frame(get_window_frame(ctx, self.config.widget_opacity))
+            .resizable(true)
+            .min_width(200.0)
+            .min_height(150.0)
+            .show(ctx, |ui| {
+                self.show_speed_analysis_widget(ui);
+            });
+    }
+
+    pub fn show_rotation_analysis_window(&mut self, ctx: &egui::Context) {
+        egui::containers::Window::new(t!("Rotation Analysis"))
+            .id("rotation_analysis_window".into())
+            .frame(get_window_frame(ctx, self.config.widget_opacity))
+            .resizable(true)
+            .min_width(200.0)
+            .min_height(150.0)
+            .show(ctx, |ui| {
+                self.show_rotation_analysis_widget(ui);
+            });
+    }
+
+    pub fn show_enabled_damage_distribution_window(&mut self, ctx: &egui::Context) {
+        egui::containers::Window::new(t!("Enabled Damage Distribution"))
+            .id("enabled_damage_distribution_window".into())
+            .frame(get_window_frame(ctx, self.config.widget_opacity))
+            .collapsible(false)
+            .resizable(true)
+            .min_width(200.0)
+            .min_height(200.0)
+            .show(ctx, |ui| {
+                self.show_enabled_damage_distribution_widget(ui);
+            });
+    }
 }
@@ -1,11 +1,22 @@
 use crate::ui::app::GraphUnit;
 use egui::{Color32, Sense, Stroke, Ui, Vec2};
-use egui_plot::{Bar, BarChart, Legend, Line, Plot, PlotPoints, Polygon};
+use egui_plot::{Bar, BarChart, Legend, Line, MarkerShape, Plot, PlotPoints, Points, Polygon};
 
-use crate::{battle::BattleContext, models::misc::Avatar};
+use crate::{
+    analysis::speed::GaugeShiftKind,
+    battle::{
+        BattleContext, cycle_at,
+        scoring::ModeMetrics,
+        summons::SummonDamage,
+        timeline::ActionKind,
+    },
+    models::misc::{Avatar, EnemyRank, Entity, Team},
+};
 
 use super::{app::App, helpers};
 
+const PREDICTED_ACTION_COUNT: usize = 8;
+
 pub struct PieSegment {
     pub points: Vec<[f64; 2]>,
     pub value: f64,
@@ -13,9 +24,45 @@ pub struct PieSegment {
 
 impl App {
     pub fn show_damage_distribution_widget(&mut self, ui: &mut Ui) {
+        let (damages, avatars, summons) = {
+            let battle_context = BattleContext::get_instance();
+            (
+                battle_context.real_time_damages.clone(),
+                battle_context.avatar_lineup.clone(),
+                battle_context.summons.clone(),
+            )
+        };
+        self.show_damage_pie(ui, "damage_pie", &damages, &avatars, &summons);
+    }
+
+    // Same pie with the damage supports enabled moved from the dealers to the supports
+    pub fn show_enabled_damage_distribution_widget(&mut self, ui: &mut Ui) {
+        let (damages, avatars) = {
+            let battle_context = BattleContext::get_instance();
+            (
+                battle_context
+                    .damage_attribution()
+                    .iter()
+                    .map(|attribution| attribution.attributed_damage.max(0.))
+                    .collect::<Vec<f64>>(),
+                battle_context.avatar_lineup.clone(),
+            )
+        };
+        self.show_damage_pie(ui, "enabled_damage_pie", &damages, &avatars, &[]);
+    }
+
+    // Summon damage is drawn as a layer over the start of its owner's slice
+    fn show_damage_pie(
+        &mut self,
+        ui: &mut Ui,
+        id: &str,
+        damages: &[f64],
+        avatars: &[Avatar],
+        summons: &[SummonDamage],
+    ) {
         let available = ui.available_size();
 
-        Plot::new("damage_pie")
+        Plot::new(id)
             // .legend(
             //     Legend::default()
             //         .position(self.config.legend_position)
@@ -32,16 +79,15 @@ impl App {
             // .allow_zoom(false)
             .allow_scroll(false)
             .show(ui, |plot_ui: &mut egui_plot::PlotUi<'_>| {
-                let battle_context = BattleContext::get_instance();
-
-                let total_damage = battle_context.total_damage as f64;
+                let total_damage = damages.iter().sum::<f64>();
                 if total_damage > 0.0 {
-                    let segments = create_pie_segments(
-                        &battle_context.real_time_damages,
-                        &battle_context.avatar_lineup,
-                    );
+                    let segments = create_pie_segments(damages, avatars);
+                    let mut start_angle = -std::f64::consts::FRAC_PI_2;
                     for (avatar, segment, i) in segments {
                         let color = helpers::get_character_color(i);
+                        let end_angle =
+                            start_angle + segment.value / total_damage * std::f64::consts::TAU;
+
                         // let percentage = segment.value / total_damage * 100.0;
 
                         let plot_points = PlotPoints::new(segment.points);
@@ -58,6 +104,29 @@ impl App {
                             // ));
 
                         plot_ui.polygon(polygon);
+
+                        if !self.config.fold_summon_damage {
+                            let mut summon_start_angle = start_angle;
+                            for summon in summons.iter().filter(|summon| summon.owner_id == avatar.id) {
+                                let summon_end_angle = summon_start_angle
+                                    + summon.damage / total_damage * std::f64::consts::TAU;
+                                let polygon = Polygon::new(
+                                    "Damage Pie",
+                                    PlotPoints::new(create_pie_slice(
+                                        summon_start_angle,
+                                        summon_end_angle,
+                                    )),
+                                )
+                                .stroke(Stroke::new(1.0, color))
+                                .fill_color(color.linear_multiply(
+                                    (self.config.pie_chart_opacity * 2.0).min(1.0),
+                                ))
+                                .id(format!("{} {}", avatar.name, summon.name));
+                                plot_ui.polygon(polygon);
+                                summon_start_angle = summon_end_angle;
+                            }
+                        }
+                        start_angle = end_angle;
                     }
                 }
             });
@@ -179,6 +248,7 @@ impl App {
 
     pub fn show_turn_damage_plot(&mut self, ui: &mut Ui) {
         let battle_context = BattleContext::get_instance();
+        let graph_wave = self.state.graph_wave;
         let available = ui.available_size();
         Plot::new("damage_plot")
             // .legend(
@@ -199,6 +269,7 @@ impl App {
                         .turn_history
                         .iter()
                         .enumerate()
+                        .filter(|(_, turn)| graph_wave.is_none_or(|wave| turn.wave == wave))
                         .map(|(turn_idx, turn)| {
                             [turn_idx as f64 + 1.0, turn.avatars_turn_damage[i]]
                         })
@@ -212,11 +283,55 @@ impl App {
                         );
                     }
                 }
+
+                // Damage dealt during enemy turns, e.g. counters and DoT ticks
+                let enemy_turns = battle_context
+                    .turn_history
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, turn)| graph_wave.is_none_or(|wave| turn.wave == wave))
+                    .filter(|(_, turn)| {
+                        turn.turn_owner
+                            .as_ref()
+                            .is_some_and(|turn_owner| turn_owner.team == Team::Enemy)
+                    })
+                    .map(|(turn_idx, turn)| [turn_idx as f64 + 1.0, turn.total_damage])
+                    .collect::<Vec<[f64; 2]>>();
+                if !enemy_turns.is_empty() {
+                    plot_ui.points(
+                        Points::new(t!("Enemy Turns"), PlotPoints::from(enemy_turns))
+                            .color(Color32::from_gray(140))
+                            .shape(MarkerShape::Square)
+                            .radius(4.0),
+                    );
+                }
+
+                let (deaths, revives) =
+                    downtime_points(&battle_context, graph_wave, |_, turn_count| {
+                        turn_count as f64 + 1.0
+                    });
+                if !deaths.is_empty() {
+                    plot_ui.points(
+                        Points::new(t!("Deaths"), PlotPoints::from(deaths))
+                            .color(Color32::RED)
+                            .shape(MarkerShape::Cross)
+                            .radius(6.0),
+                    );
+                }
+                if !revives.is_empty() {
+                    plot_ui.points(
+                        Points::new(t!("Revives"), PlotPoints::from(revives))
+                            .color(Color32::GREEN)
+                            .shape(MarkerShape::Diamond)
+                            .radius(5.0),
+                    );
+                }
             });
     }
 
     pub fn show_av_damage_plot(&mut self, ui: &mut Ui) {
         let battle_context = BattleContext::get_instance();
+        let graph_wave = self.state.graph_wave;
         let available = ui.available_size();
         Plot::new("damage_plot")
             // .legend(
@@ -236,6 +351,7 @@ impl App {
                     let points = battle_context
                         .av_history
                         .iter()
+                        .filter(|turn| graph_wave.is_none_or(|wave| turn.wave == wave))
                         .map(|turn| [turn.action_value, turn.avatars_turn_damage[i]])
                         .collect::<Vec<[f64; 2]>>();
 
@@ -247,6 +363,25 @@ impl App {
                         );
                     }
                 }
+
+                let (deaths, revives) =
+                    downtime_points(&battle_context, graph_wave, |action_value, _| action_value);
+                if !deaths.is_empty() {
+                    plot_ui.points(
+                        Points::new(t!("Deaths"), PlotPoints::from(deaths))
+                            .color(Color32::RED)
+                            .shape(MarkerShape::Cross)
+                            .radius(6.0),
+                    );
+                }
+                if !revives.is_empty() {
+                    plot_ui.points(
+                        Points::new(t!("Revives"), PlotPoints::from(revives))
+                            .color(Color32::GREEN)
+                            .shape(MarkerShape::Diamond)
+                            .radius(5.0),
+                    );
+                }
             });
     }
 
@@ -259,16 +394,85 @@ impl App {
                     GraphUnit::ActionValue,
                     t!("Action Value"),
                 );
+                ui.radio_value(
+                    &mut self.state.graph_x_unit,
+                    GraphUnit::EnemyHp,
+                    t!("Enemy HP"),
+                );
+
+                let waves = BattleContext::get_instance()
+                    .wave_segments
+                    .iter()
+                    .map(|segment| segment.wave)
+                    .collect::<Vec<u32>>();
+                if self.state.graph_wave.is_some_and(|wave| !waves.contains(&wave)) {
+                    self.state.graph_wave = None;
+                }
+                let selected_text = match self.state.graph_wave {
+                    Some(wave) => format!("{} {}", t!("Wave"), wave),
+                    None => t!("All Waves").to_string(),
+                };
+                egui::ComboBox::from_id_salt("graph_wave")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.state.graph_wave, None, t!("All Waves"));
+                        for wave in waves {
+                            ui.selectable_value(
+                                &mut self.state.graph_wave,
+                                Some(wave),
+                                format!("{} {}", t!("Wave"), wave),
+                            );
+                        }
+                    });
             });
             ui.add_space(8.0);
 
             match self.state.graph_x_unit {
                 GraphUnit::Turn => self.show_turn_damage_plot(ui),
                 GraphUnit::ActionValue => self.show_av_damage_plot(ui),
+                GraphUnit::EnemyHp => self.show_enemy_hp_plot(ui),
             }
         });
     }
 
+    // Shows which targets soaked the rotation, one line per enemy from spawn to defeat
+    pub fn show_enemy_hp_plot(&mut self, ui: &mut Ui) {
+        let battle_context = BattleContext::get_instance();
+        let graph_wave = self.state.graph_wave;
+        let available = ui.available_size();
+        Plot::new("enemy_hp_plot")
+            .height(available.y)
+            .width(available.x)
+            .include_y(0.0)
+            .x_axis_label(t!("Action Value"))
+            .y_axis_label(t!("HP"))
+            .y_axis_formatter(|y, _| helpers::format_damage(y.value))
+            .show(ui, |plot_ui| {
+                for (i, enemy) in battle_context
+                    .enemies
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, enemy)| graph_wave.is_none_or(|wave| enemy.wave == wave))
+                {
+                    let points = battle_context
+                        .hp_samples_of(enemy.uid)
+                        .map(|sample| [sample.action_value, sample.hp])
+                        .collect::<Vec<[f64; 2]>>();
+
+                    if !points.is_empty() {
+                        plot_ui.line(
+                            Line::new(
+                                format!("{} ({})", enemy.name, enemy.uid),
+                                PlotPoints::from(points),
+                            )
+                            .color(helpers::get_character_color(i))
+                            .width(2.0),
+                        );
+                    }
+                }
+            });
+    }
+
     pub fn show_battle_metrics_widget(&mut self, ui: &mut Ui) {
         let battle_context = BattleContext::get_instance();
 
@@ -286,10 +490,42 @@ impl App {
 
                         ui.label(format!("{:.2}", battle_context.real_time_damages[i],));
                     });
+                    if !self.config.fold_summon_damage {
+                        for summon in battle_context.summons_of(avatar.id) {
+                            ui.horizontal(|ui| {
+                                ui.add_space(12.0);
+                                ui.label(format!("↳ {}", summon.name));
+                                ui.label(format!("{:.2}", summon.damage));
+                            });
+                        }
+                    }
                 }
             });
         });
 
+        let technique_phase = &battle_context.technique_phase;
+        if technique_phase.total_damage > 0.0 {
+            egui::CollapsingHeader::new(format!(
+                "{}: {:.2}",
+                t!("Technique Damage"),
+                technique_phase.total_damage
+            ))
+            .id_salt("technique_damage_header")
+            .show(ui, |ui| {
+                ui.vertical(|ui| {
+                    for (i, avatar) in battle_context.avatar_lineup.iter().enumerate() {
+                        let damage = technique_phase.avatars_damage.get(i).copied().unwrap_or(0.0);
+                        if damage > 0.0 {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{}", avatar.name));
+                                ui.label(format!("{:.2}", damage));
+                            });
+                        }
+                    }
+                });
+            });
+        }
+
         let current_action_value =
             battle_context.action_value - battle_context.last_wave_action_value;
 
@@ -338,6 +574,59 @@ impl App {
                     }
                 });
             });
+
+        let projection = battle_context.cycle_projection();
+        egui::CollapsingHeader::new(format!(
+            "{}: {}",
+            t!("Cycle"),
+            cycle_at(battle_context.action_value)
+        ))
+        .id_salt("cycle_header")
+        .show(ui, |ui| {
+            ui.label(format!(
+                "{}: {}",
+                t!("Projected Cycles Remaining"),
+                if projection.projected_clear_action_value.is_finite() {
+                    projection.cycles_remaining.to_string()
+                } else {
+                    "-".to_string()
+                }
+            ));
+            ui.label(format!(
+                "{}: {:.2}",
+                t!("Remaining Enemy HP"),
+                projection.remaining_enemy_hp
+            ));
+            if projection.on_track_for_zero_cycle {
+                ui.label(t!("On track for a 0-cycle clear"));
+            }
+
+            for segment in &battle_context.cycle_segments {
+                egui::CollapsingHeader::new(format!(
+                    "{} {} ({:.0}-{:.0} {}): {:.2}",
+                    t!("Cycle"),
+                    segment.cycle,
+                    segment.start_action_value,
+                    segment.end_action_value,
+                    t!("AV"),
+                    segment.total_damage
+                ))
+                .id_salt(("cycle_segment", segment.cycle))
+                .show(ui, |ui| {
+                    for (i, avatar) in battle_context.avatar_lineup.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}", avatar.name));
+                            ui.label(format!(
+                                "{:.2} ({} {})",
+                                segment.avatars_damage.get(i).copied().unwrap_or(0.0),
+                                segment.avatars_actions.get(i).copied().unwrap_or(0),
+                                t!("Actions")
+                            ));
+                        });
+                    }
+                });
+            }
+        });
     }
 
     pub fn show_enemy_stats_widget(&mut self, ui: &mut Ui) {
@@ -353,18 +642,360 @@ impl App {
                     .find(|(_, x)| x.entity == *enemy)
                     .map(|(i, _)| i)
                 {
+                    let enemy_info = &battle_context.enemies[i];
                     ui.horizontal(|ui| {
-                        ui.label(format!("{}: ", &battle_context.enemies[i].name));
+                        ui.label(format!("{}: ", &enemy_info.name));
                         ui.label(format!(
                             "{:.2} {}",
                             battle_context.battle_enemies[i].battle_stats.hp,
                             t!("HP")
                         ));
+                        if enemy_info.rank != EnemyRank::Normal {
+                            ui.label(format!("({:?})", enemy_info.rank));
+                        }
+                        if let Some(phase) = battle_context
+                            .current_phase(enemy_info.uid)
+                            .filter(|phase| phase.phase > 1)
+                        {
+                            ui.label(format!("{} {}", t!("Phase"), phase.phase));
+                        }
                     });
+                    if !enemy_info.weaknesses.is_empty() {
+                        let weaknesses = enemy_info
+                            .weaknesses
+                            .iter()
+                            .map(|element| element.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        ui.label(format!("{}: {}", t!("Weaknesses"), weaknesses));
+                    }
                 }
             }
         });
     }
+
+    pub fn show_action_timeline_widget(&mut self, ui: &mut Ui) {
+        let battle_context = BattleContext::get_instance();
+        let timeline = battle_context.action_timeline();
+        let predicted = battle_context.predicted_actions(PREDICTED_ACTION_COUNT);
+
+        // One lane per avatar and a shared one for enemies
+        let enemy_lane = battle_context.avatar_lineup.len();
+        let lane_names = battle_context
+            .avatar_lineup
+            .iter()
+            .map(|avatar| avatar.name.clone())
+            .chain([t!("Enemies").to_string()])
+            .collect::<Vec<String>>();
+        let lane_of = |entity: &Entity| match entity.team {
+            Team::Player => battle_context
+                .avatar_lineup
+                .iter()
+                .position(|avatar| avatar.id == entity.uid),
+            Team::Enemy => Some(enemy_lane),
+        };
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("{}:", t!("Next")));
+            for action in &predicted {
+                let color = lane_of(&action.entity)
+                    .map_or(Color32::GRAY, |lane| lane_color(lane, enemy_lane));
+                ui.colored_label(
+                    color,
+                    format!(
+                        "{} ({:.0})",
+                        battle_context.entity_name(&action.entity),
+                        action.action_value
+                    ),
+                );
+            }
+        });
+        ui.add_space(8.0);
+
+        let formatter_lane_names = lane_names.clone();
+        let available = ui.available_size();
+        Plot::new("action_timeline_plot")
+            .height(available.y)
+            .width(available.x)
+            .include_y(-0.5)
+            .include_y(lane_names.len() as f64 - 0.5)
+            .x_axis_label(t!("Action Value"))
+            .y_axis_formatter(move |y, _| {
+                let lane = y.value.round();
+                if (y.value - lane).abs() > f64::EPSILON || lane < 0. {
+                    return String::new();
+                }
+                formatter_lane_names
+                    .get(lane as usize)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .show(ui, |plot_ui| {
+                for (lane, name) in lane_names.iter().enumerate() {
+                    let color = lane_color(lane, enemy_lane);
+                    for (kind, shape) in [
+                        (ActionKind::Turn, MarkerShape::Circle),
+                        (ActionKind::Ultimate, MarkerShape::Diamond),
+                        (ActionKind::FollowUp, MarkerShape::Square),
+                    ] {
+                        let points = timeline
+                            .iter()
+                            .filter(|action| action.kind == kind && lane_of(&action.entity) == Some(lane))
+                            .map(|action| [action.action_value, lane as f64])
+                            .collect::<Vec<[f64; 2]>>();
+                        if !points.is_empty() {
+                            plot_ui.points(
+                                Points::new(name.as_str(), PlotPoints::from(points))
+                                    .shape(shape)
+                                    .radius(4.0)
+                                    .color(color),
+                            );
+                        }
+                    }
+
+                    // Predicted turns are drawn hollow
+                    let points = predicted
+                        .iter()
+                        .filter(|action| lane_of(&action.entity) == Some(lane))
+                        .map(|action| [action.action_value, lane as f64])
+                        .collect::<Vec<[f64; 2]>>();
+                    if !points.is_empty() {
+                        plot_ui.points(
+                            Points::new(name.as_str(), PlotPoints::from(points))
+                                .shape(MarkerShape::Circle)
+                                .filled(false)
+                                .radius(4.0)
+                                .color(color),
+                        );
+                    }
+                }
+            });
+    }
+
+    pub fn show_speed_analysis_widget(&mut self, ui: &mut Ui) {
+        let reports = BattleContext::get_instance().speed_report();
+
+        for report in &reports {
+            egui::CollapsingHeader::new(format!(
+                "{}: {:.1} {}, {} {}",
+                report.name,
+                report.speed,
+                t!("SPD"),
+                report.actions,
+                t!("Actions")
+            ))
+            .id_salt(("speed_report", report.avatar_id))
+            .show(ui, |ui| {
+                let format_interval =
+                    |interval: Option<f64>| interval.map_or("-".to_string(), |v| format!("{v:.2}"));
+                ui.label(format!(
+                    "{}: {} ({}: {})",
+                    t!("Average AV Between Actions"),
+                    format_interval(report.average_action_interval),
+                    t!("Expected"),
+                    format_interval(report.expected_action_interval)
+                ));
+                ui.label(format!(
+                    "{}: {}, {}: {}",
+                    t!("Advances"),
+                    report.count_shifts(GaugeShiftKind::Advance),
+                    t!("Delays"),
+                    report.count_shifts(GaugeShiftKind::Delay)
+                ));
+                for shift in &report.gauge_shifts {
+                    let kind = match shift.kind {
+                        GaugeShiftKind::Advance => t!("Advance"),
+                        GaugeShiftKind::Delay => t!("Delay"),
+                    };
+                    ui.label(format!(
+                        "  {:.2} {}: {} {:.1}%",
+                        shift.action_value,
+                        t!("AV"),
+                        kind,
+                        shift.ratio * 100.
+                    ));
+                }
+
+                ui.separator();
+                for (cycle, (actual, theoretical)) in report
+                    .actions_per_cycle
+                    .iter()
+                    .zip(&report.theoretical_actions_per_cycle)
+                    .enumerate()
+                {
+                    ui.label(format!(
+                        "{} {}: {} / {:.2} {}",
+                        t!("Cycle"),
+                        cycle,
+                        actual,
+                        theoretical,
+                        t!("Actions")
+                    ));
+                }
+            });
+        }
+    }
+
+    pub fn show_rotation_analysis_widget(&mut self, ui: &mut Ui) {
+        let reports = BattleContext::get_instance().rotation_report();
+
+        for report in &reports {
+            egui::CollapsingHeader::new(format!(
+                "{}: {}",
+                report.name,
+                report.rotation.as_deref().unwrap_or("-")
+            ))
+            .id_salt(("rotation_report", report.avatar_id))
+            .show(ui, |ui| {
+                if !report.wasted_ultimates.is_empty() {
+                    ui.colored_label(
+                        Color32::from_rgb(224, 115, 107),
+                        format!(
+                            "{}: {}",
+                            t!("Wasted Ultimates"),
+                            report
+                                .wasted_ultimates
+                                .iter()
+                                .map(|action_value| format!("{action_value:.2}"))
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        ),
+                    );
+                }
+
+                for sequence in &report.top_sequences {
+                    ui.label(format!(
+                        "{} ({:.2} {}): {:.2}",
+                        sequence.sequence,
+                        sequence.action_value,
+                        t!("AV"),
+                        sequence.damage
+                    ));
+                }
+
+                ui.separator();
+                for (cycle, counts) in report.uses_per_cycle.iter().enumerate() {
+                    ui.label(format!(
+                        "{} {}: {} {}, {} {}, {} {}, {} {}",
+                        t!("Cycle"),
+                        cycle,
+                        counts.basic,
+                        t!("Basic"),
+                        counts.skill,
+                        t!("Skill"),
+                        counts.ultimate,
+                        t!("Ultimate"),
+                        counts.talent,
+                        t!("Talent")
+                    ));
+                }
+            });
+        }
+    }
+
+    pub fn show_mode_metrics_widget(&mut self, ui: &mut Ui) {
+        let battle_context = BattleContext::get_instance();
+
+        let Some(metrics) = battle_context.mode_metrics() else {
+            ui.label(t!("No mode metrics for this stage"));
+            return;
+        };
+
+        ui.vertical(|ui| match metrics {
+            ModeMetrics::MOC(metrics) => {
+                ui.label(format!(
+                    "{}: {} / {}",
+                    t!("Cycles Used"),
+                    metrics.cycles_used,
+                    metrics.cycle_limit
+                ));
+                for (i, action_value) in metrics.wave_action_values.iter().enumerate() {
+                    ui.label(format!("{} {}: {:.2} {}", t!("Wave"), i + 1, action_value, t!("AV")));
+                }
+            }
+            ModeMetrics::PF(metrics) => {
+                ui.label(format!("{}: {}", t!("Kills"), metrics.kills));
+                ui.label(format!("{}: {:.2}", t!("Overkill Damage"), metrics.overkill_damage));
+            }
+            ModeMetrics::AS(metrics) => {
+                ui.label(format!(
+                    "{}: {:.2} / {:.2} {}",
+                    metrics.boss_name, metrics.boss_hp, metrics.boss_max_hp, t!("HP")
+                ));
+                ui.add(egui::ProgressBar::new(metrics.progress as f32).show_percentage());
+                ui.label(format!("{}: {}", t!("Cycles Left"), metrics.cycles_left));
+            }
+        });
+
+        let Some(run) = &battle_context.run else {
+            return;
+        };
+
+        ui.separator();
+        let totals = run.totals();
+        let header = if run.is_complete() {
+            t!("Run")
+        } else {
+            t!("Run (in progress)")
+        };
+        egui::CollapsingHeader::new(format!(
+            "{}: {} {}",
+            header,
+            totals.cycles_used,
+            t!("Cycles")
+        ))
+        .id_salt("run_header")
+        .default_open(true)
+        .show(ui, |ui| {
+            for half in 1..=2 {
+                let Some(run_half) = run.half(half) else {
+                    ui.label(format!("{} {}: -", t!("Half"), half));
+                    continue;
+                };
+                ui.label(format!(
+                    "{} {}: {} {}, {:.2} {}",
+                    t!("Half"),
+                    half,
+                    run_half.cycles_used,
+                    t!("Cycles"),
+                    run_half.total_damage,
+                    t!("Damage")
+                ));
+            }
+            ui.label(format!("{}: {:.2}", t!("Total Damage"), totals.total_damage));
+        });
+    }
+}
+
+// Avatar deaths and revives on the x axis of the real-time graph, placed by AV and turns completed
+fn downtime_points(
+    battle_context: &BattleContext,
+    graph_wave: Option<u32>,
+    x: impl Fn(f64, usize) -> f64,
+) -> (Vec<[f64; 2]>, Vec<[f64; 2]>) {
+    let deaths = battle_context
+        .avatar_deaths
+        .iter()
+        .filter(|death| graph_wave.is_none_or(|wave| death.wave == wave))
+        .collect::<Vec<_>>();
+    let death_points = deaths
+        .iter()
+        .map(|death| [x(death.action_value, death.turn_count), 0.0])
+        .collect();
+    let revive_points = deaths
+        .iter()
+        .filter_map(|death| death.revived_action_value.zip(death.revived_turn_count))
+        .map(|(action_value, turn_count)| [x(action_value, turn_count), 0.0])
+        .collect();
+    (death_points, revive_points)
+}
+
+fn lane_color(lane: usize, enemy_lane: usize) -> Color32 {
+    if lane == enemy_lane {
+        Color32::from_gray(140)
+    } else {
+        helpers::get_character_color(lane)
+    }
 }
 
 fn create_bar_data(
@@ -379,8 +1010,8 @@ fn create_bar_data(
 }
 
 fn create_pie_segments(
-    real_time_damages: &Vec<f64>,
-    avatars: &Vec<Avatar>,
+    real_time_damages: &[f64],
+    avatars: &[Avatar],
 ) -> Vec<(Avatar, PieSegment, usize)> {
     let total_damage = real_time_damages.into_iter().sum::<f64>();
     let mut segments = Vec::new();